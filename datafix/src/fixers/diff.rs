@@ -0,0 +1,525 @@
+use alloc::{
+    boxed::Box,
+    collections::{btree_map::BTreeMap, btree_set::BTreeSet},
+    format,
+    string::String,
+    vec::Vec,
+};
+
+use crate::serialization::{CodecOps, Dynamic};
+
+use super::{ObjectType, PathSegment, Predicates, Rules, Type, TypeRewriteRule};
+
+/// A single structural difference between two [`Type`]s, found by [`Type::diff`].
+///
+/// `parent` is the path of the value containing the field, as accepted by [`Rules::at_segments`],
+/// and is empty for fields of the root object.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TypeChange {
+    /// A field that only exists in the new type.
+    Added {
+        parent: Vec<PathSegment>,
+        field: String,
+        ty: Type,
+    },
+    /// A field that only exists in the old type.
+    Removed {
+        parent: Vec<PathSegment>,
+        field: String,
+        ty: Type,
+    },
+    /// A value that exists in both types, but with a different type.
+    /// If the value is not a field, such as the elements of a list, `field` is empty and `parent` is
+    /// the path of the value itself. If the root types differ, both are empty.
+    Retyped {
+        parent: Vec<PathSegment>,
+        field: String,
+        from: Type,
+        to: Type,
+    },
+}
+
+impl TypeChange {
+    pub fn parent(&self) -> &[PathSegment] {
+        match self {
+            TypeChange::Added { parent, .. }
+            | TypeChange::Removed { parent, .. }
+            | TypeChange::Retyped { parent, .. } => parent,
+        }
+    }
+
+    pub fn field(&self) -> &str {
+        match self {
+            TypeChange::Added { field, .. }
+            | TypeChange::Removed { field, .. }
+            | TypeChange::Retyped { field, .. } => field,
+        }
+    }
+
+    /// Returns the full path of the changed field for display, such as `settings.display[*].font_size`.
+    /// Variants of a tagged choice are written as `[tag=name]`, such as `[event=click].x`.
+    pub fn path(&self) -> String {
+        let mut path = String::new();
+        for segment in self.parent() {
+            match segment {
+                PathSegment::Field(name) if path.is_empty() => path.push_str(name),
+                PathSegment::Field(name) => path.push_str(&format!(".{name}")),
+                PathSegment::Index(index) => path.push_str(&format!("[{index}]")),
+                PathSegment::Variant { tag, name } => path.push_str(&format!("[{tag}={name}]")),
+                PathSegment::Each => path.push_str("[*]"),
+            }
+        }
+        match (path.is_empty(), self.field()) {
+            (_, "") => path,
+            (true, field) => field.into(),
+            (false, field) => format!("{path}.{field}"),
+        }
+    }
+}
+
+/// The changes needed to turn one [`Type`] into another. See [`Type::diff`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct TypeDiff {
+    changes: Vec<TypeChange>,
+    /// Parents that are, or are nested inside of, an optional value, so they may be absent from the data.
+    optional_parents: BTreeSet<Vec<PathSegment>>,
+}
+
+impl Type {
+    /// Compares two types and returns every field that was added, removed or changed its type between `old` and `new`.
+    ///
+    /// This recurses into objects, lists, optional values, recursive types, and the variants of tagged choices with
+    /// the same tag and variants. Any other difference, including changes inside of maps and either types,
+    /// is reported as a single [`TypeChange::Retyped`] of the whole value.
+    pub fn diff(old: &Type, new: &Type) -> TypeDiff {
+        let mut diff = TypeDiff {
+            changes: Vec::new(),
+            optional_parents: BTreeSet::new(),
+        };
+        diff_into(Vec::new(), false, old, new, &mut diff);
+        diff
+    }
+}
+
+fn diff_into(path: Vec<PathSegment>, optional: bool, old: &Type, new: &Type, diff: &mut TypeDiff) {
+    match (old, new) {
+        _ if old == new => {}
+        (Type::Object(old), Type::Object(new)) => diff_objects(path, optional, old, new, diff),
+        (Type::Array(old), Type::Array(new)) => {
+            diff_into(
+                [path, [PathSegment::Each].into()].concat(),
+                optional,
+                old.ty(),
+                new.ty(),
+                diff,
+            );
+        }
+        (Type::Optional(old), Type::Optional(new)) => {
+            diff_into(path, true, old.ty(), new.ty(), diff)
+        }
+        (Type::Recursive(old), Type::Recursive(new)) if old.name() == new.name() => {
+            diff_into(path, optional, old.ty(), new.ty(), diff)
+        }
+        (Type::TaggedChoice(old), Type::TaggedChoice(new))
+            if old.tag() == new.tag()
+                && old
+                    .variants()
+                    .map(|(name, _)| name)
+                    .eq(new.variants().map(|(name, _)| name)) =>
+        {
+            for ((name, old_variant), (_, new_variant)) in old.variants().zip(new.variants()) {
+                let variant = PathSegment::Variant {
+                    tag: old.tag().into(),
+                    name: name.into(),
+                };
+                diff_into(
+                    [path.clone(), [variant].into()].concat(),
+                    optional,
+                    old_variant,
+                    new_variant,
+                    diff,
+                );
+            }
+        }
+        _ => {
+            let (parent, field) = match path.split_last() {
+                Some((PathSegment::Field(field), parent)) => (parent.to_vec(), field.clone()),
+                _ => (path, String::new()),
+            };
+            diff.changes.push(TypeChange::Retyped {
+                parent,
+                field,
+                from: old.clone(),
+                to: new.clone(),
+            })
+        }
+    }
+}
+
+fn diff_objects(
+    path: Vec<PathSegment>,
+    optional: bool,
+    old: &ObjectType,
+    new: &ObjectType,
+    diff: &mut TypeDiff,
+) {
+    if optional {
+        diff.optional_parents.insert(path.clone());
+    }
+    for (name, old_field) in old.fields() {
+        match new.get(name) {
+            Ok(new_field) => diff_into(
+                [path.clone(), [PathSegment::Field(name.into())].into()].concat(),
+                optional,
+                old_field,
+                &new_field,
+                diff,
+            ),
+            Err(_) => diff.changes.push(TypeChange::Removed {
+                parent: path.clone(),
+                field: name.into(),
+                ty: old_field.clone(),
+            }),
+        }
+    }
+    for (name, new_field) in new.fields() {
+        if old.get(name).is_err() {
+            diff.changes.push(TypeChange::Added {
+                parent: path.clone(),
+                field: name.into(),
+                ty: new_field.clone(),
+            });
+        }
+    }
+}
+
+impl TypeDiff {
+    pub fn changes(&self) -> &[TypeChange] {
+        &self.changes
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    /// Generates a rule for every change that can be migrated without guessing:
+    /// - Added fields get the value in `defaults` for their full path, see [`TypeChange::path`].
+    ///   Added optional fields need no default.
+    /// - Removed fields are removed.
+    ///
+    /// Everything else is returned as a hole, left for the developer to write a rule for. This includes
+    /// retyped fields, added fields without a default, fields removed and added with the same type
+    /// in the same object, since those may be renames, and changes inside of optional values, since
+    /// [`Rules::at_segments`] fails when the optional value is absent.
+    pub fn into_rules<O: CodecOps + 'static>(
+        self,
+        defaults: &BTreeMap<String, O::T>,
+    ) -> GeneratedRules<O>
+    where
+        O::T: 'static,
+    {
+        let may_be_rename = |change: &TypeChange, ty: &Type| {
+            self.changes.iter().any(|other| {
+                other.parent() == change.parent()
+                    && match (change, other) {
+                        (TypeChange::Added { .. }, TypeChange::Removed { ty: other, .. })
+                        | (TypeChange::Removed { .. }, TypeChange::Added { ty: other, .. }) => {
+                            other == ty
+                        }
+                        _ => false,
+                    }
+            })
+        };
+
+        let mut rule: Box<dyn TypeRewriteRule<O>> = Box::new(Rules::identity());
+        let mut holes = Vec::new();
+        for change in &self.changes {
+            if self.optional_parents.contains(change.parent()) {
+                holes.push(change.clone());
+                continue;
+            }
+            let next: Box<dyn TypeRewriteRule<O>> = match change {
+                TypeChange::Added { field, ty, .. } if !may_be_rename(change, ty) => {
+                    match (defaults.get(&change.path()), ty) {
+                        (_, Type::Optional(_)) => continue,
+                        (Some(default), _) => {
+                            let default = default.clone();
+                            let ty = ty.clone();
+                            Box::new(Rules::new_field(
+                                field,
+                                move |ctx| Dynamic::new(default.clone(), ctx.ops()),
+                                move |_| ty.clone(),
+                            ))
+                        }
+                        (None, _) => {
+                            holes.push(change.clone());
+                            continue;
+                        }
+                    }
+                }
+                TypeChange::Removed { field, ty, .. } if !may_be_rename(change, ty) => {
+                    if matches!(ty, Type::Optional(_)) {
+                        Box::new(Rules::when(
+                            Predicates::has_field(field),
+                            Rules::remove_field(field),
+                        ))
+                    } else {
+                        Box::new(Rules::remove_field(field))
+                    }
+                }
+                _ => {
+                    holes.push(change.clone());
+                    continue;
+                }
+            };
+            let next = match change.parent() {
+                [] => next,
+                parent => Box::new(Rules::at_segments(parent.to_vec(), next)),
+            };
+            rule = Box::new(rule.and_then(next));
+        }
+        GeneratedRules { rule, holes }
+    }
+}
+
+/// The result of [`TypeDiff::into_rules`].
+pub struct GeneratedRules<O: CodecOps> {
+    rule: Box<dyn TypeRewriteRule<O>>,
+    holes: Vec<TypeChange>,
+}
+
+impl<O: CodecOps> GeneratedRules<O> {
+    /// The rule migrating every change that was not a hole.
+    pub fn rule(&self) -> &dyn TypeRewriteRule<O> {
+        self.rule.as_ref()
+    }
+
+    pub fn into_rule(self) -> Box<dyn TypeRewriteRule<O>> {
+        self.rule
+    }
+
+    /// The changes that no rule was generated for.
+    pub fn holes(&self) -> &[TypeChange] {
+        &self.holes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{collections::btree_map::BTreeMap, string::String, vec, vec::Vec};
+
+    use crate::{
+        fixers::{
+            ArrayType, MapType, ObjectType, OptionalType, PathSegment, RecursiveType,
+            TaggedChoiceType, Type,
+        },
+        serialization::{CodecOps, json::JsonOps},
+    };
+
+    use super::TypeChange;
+
+    #[test]
+    fn diff_types() {
+        let old = Type::Object(
+            ObjectType::new()
+                .field("name", Type::String)
+                .field("level", Type::Int)
+                .field(
+                    "items",
+                    Type::Array(ArrayType::new(Type::Object(
+                        ObjectType::new().field("id", Type::String),
+                    ))),
+                ),
+        );
+        let new = Type::Object(
+            ObjectType::new()
+                .field("name", Type::String)
+                .field("level", Type::Long)
+                .field(
+                    "items",
+                    Type::Array(ArrayType::new(Type::Object(
+                        ObjectType::new()
+                            .field("id", Type::String)
+                            .field("count", Type::Int),
+                    ))),
+                ),
+        );
+
+        let diff = Type::diff(&old, &new);
+        assert_eq!(
+            diff.changes(),
+            [
+                TypeChange::Added {
+                    parent: vec![PathSegment::Field("items".into()), PathSegment::Each],
+                    field: "count".into(),
+                    ty: Type::Int,
+                },
+                TypeChange::Retyped {
+                    parent: vec![],
+                    field: "level".into(),
+                    from: Type::Int,
+                    to: Type::Long,
+                },
+            ]
+        );
+        assert_eq!(diff.changes()[0].path(), "items[*].count");
+        assert!(Type::diff(&new, &new).is_empty());
+    }
+
+    #[test]
+    fn generate_rules_from_diff() {
+        let old = Type::Object(
+            ObjectType::new()
+                .field("name", Type::String)
+                .field("legacy", Type::Boolean)
+                .field("colour", Type::String),
+        );
+        let new = Type::Object(
+            ObjectType::new()
+                .field("name", Type::String)
+                .field("volume", Type::Int)
+                .field("nickname", Type::Optional(OptionalType::new(Type::String)))
+                .field("color", Type::String)
+                .field("difficulty", Type::Int),
+        );
+
+        let defaults = BTreeMap::from([(String::from("volume"), JsonOps.create_int(&5))]);
+        let generated = Type::diff(&old, &new).into_rules::<JsonOps>(&defaults);
+
+        let holes = generated
+            .holes()
+            .iter()
+            .map(|hole| hole.path())
+            .collect::<Vec<_>>();
+        assert_eq!(holes, ["colour", "color", "difficulty"]);
+
+        let value = json::parse(r#"{ "name": "Steve", "legacy": true, "colour": "red" }"#).unwrap();
+        assert_eq!(
+            generated.rule().fix_data(JsonOps, value),
+            json::parse(r#"{ "name": "Steve", "colour": "red", "volume": 5 }"#).unwrap()
+        );
+        assert_eq!(
+            generated.rule().fix_type(old),
+            Type::Object(
+                ObjectType::new()
+                    .field("name", Type::String)
+                    .field("volume", Type::Int)
+                    .field("colour", Type::String),
+            )
+        );
+    }
+
+    #[test]
+    fn changes_inside_optional_values_are_holes() {
+        let settings = |fields: ObjectType| {
+            Type::Object(ObjectType::new().field("name", Type::String).field(
+                "settings",
+                Type::Optional(OptionalType::new(Type::Object(fields))),
+            ))
+        };
+        let old = settings(ObjectType::new().field("legacy", Type::Boolean));
+        let new = settings(ObjectType::new().field("volume", Type::Int));
+
+        let defaults = BTreeMap::from([(String::from("settings.volume"), JsonOps.create_int(&5))]);
+        let generated = Type::diff(&old, &new).into_rules::<JsonOps>(&defaults);
+        let holes = generated
+            .holes()
+            .iter()
+            .map(|hole| hole.path())
+            .collect::<Vec<_>>();
+        assert_eq!(holes, ["settings.legacy", "settings.volume"]);
+
+        let value = json::parse(r#"{ "name": "Steve" }"#).unwrap();
+        assert_eq!(
+            JsonOps
+                .try_repair(value.clone(), generated.into_rule())
+                .unwrap(),
+            value
+        );
+    }
+
+    #[test]
+    fn field_names_are_not_parsed_as_paths() {
+        let old = Type::Object(
+            ObjectType::new().field("a.b", Type::Object(ObjectType::new().field("x", Type::Int))),
+        );
+        let new = Type::Object(ObjectType::new().field("a.b", Type::Object(ObjectType::new())));
+
+        let generated = Type::diff(&old, &new).into_rules::<JsonOps>(&BTreeMap::new());
+        assert!(generated.holes().is_empty());
+        assert_eq!(
+            generated
+                .rule()
+                .fix_data(JsonOps, json::parse(r#"{ "a.b": { "x": 1 } }"#).unwrap()),
+            json::parse(r#"{ "a.b": {} }"#).unwrap()
+        );
+        assert_eq!(generated.rule().fix_type(old), new);
+    }
+
+    #[test]
+    fn diff_choices_and_recursive_types() {
+        let event = |click: ObjectType| {
+            Type::TaggedChoice(
+                TaggedChoiceType::new("event")
+                    .variant("click", Type::Object(click))
+                    .variant("load", Type::Object(ObjectType::new())),
+            )
+        };
+        let old = event(
+            ObjectType::new()
+                .field("x", Type::Int)
+                .field("legacy", Type::Boolean),
+        );
+        let new = event(ObjectType::new().field("x", Type::Int));
+
+        let diff = Type::diff(&old, &new);
+        assert_eq!(
+            diff.changes()
+                .iter()
+                .map(|change| change.path())
+                .collect::<Vec<_>>(),
+            ["[event=click].legacy"]
+        );
+        let generated = diff.into_rules::<JsonOps>(&BTreeMap::new());
+        assert_eq!(
+            generated.rule().fix_data(
+                JsonOps,
+                json::parse(r#"{ "event": "click", "x": 1, "legacy": true }"#).unwrap()
+            ),
+            json::parse(r#"{ "event": "click", "x": 1 }"#).unwrap()
+        );
+        let load = json::parse(r#"{ "event": "load", "legacy": true }"#).unwrap();
+        assert_eq!(generated.rule().fix_data(JsonOps, load.clone()), load);
+
+        let node = |value: Type| {
+            Type::Recursive(RecursiveType::new(
+                "node",
+                Type::Object(ObjectType::new().field("value", value).field(
+                    "next",
+                    Type::Optional(OptionalType::new(Type::Named("node".into()))),
+                )),
+            ))
+        };
+        assert_eq!(
+            Type::diff(&node(Type::Int), &node(Type::Long)).changes(),
+            [TypeChange::Retyped {
+                parent: vec![],
+                field: "value".into(),
+                from: Type::Int,
+                to: Type::Long,
+            }]
+        );
+
+        // Maps are compared as a whole.
+        let map = |value: Type| Type::Map(MapType::new(value));
+        assert_eq!(
+            Type::diff(&map(Type::Int), &map(Type::Long)).changes(),
+            [TypeChange::Retyped {
+                parent: vec![],
+                field: "".into(),
+                from: map(Type::Int),
+                to: map(Type::Long),
+            }]
+        );
+    }
+}
@@ -0,0 +1,614 @@
+use alloc::{collections::btree_set::BTreeSet, format, sync::Arc, vec::Vec};
+
+use crate::{
+    result::{CodecError, CodecResult, DataError, DataResult},
+    serialization::{CodecOps, Context},
+};
+
+use super::{ReversibleRule, Schema, TypeReference, TypeRewriteRule};
+
+/// A [`DataFixer`] upgrades data between versions by running every [`TypeRewriteRule`] registered
+/// for the versions in between, in ascending order. If every rule in between is a [`ReversibleRule`],
+/// data can also be downgraded, in which case the rules are reverted in descending order.
+///
+/// Rules can either apply to a whole value, or be attached to a [`TypeReference`] so they only run
+/// when data of that type is updated with [`DataFixer::update_type`].
+///
+/// A [`DataFixer`] is created using a [`DataFixerBuilder`].
+pub struct DataFixer<O: CodecOps> {
+    fixes: Vec<VersionFix<O>>,
+    schemas: Vec<Arc<Schema>>,
+    current_version: u32,
+    mode: FixMode,
+}
+
+struct VersionFix<O: CodecOps> {
+    version: u32,
+    reference: Option<TypeReference>,
+    rule: Arc<dyn TypeRewriteRule<O>>,
+    reversible: Option<Arc<dyn ReversibleRule<O>>>,
+}
+
+impl<O: CodecOps> Clone for VersionFix<O> {
+    fn clone(&self) -> Self {
+        VersionFix {
+            version: self.version,
+            reference: self.reference.clone(),
+            rule: self.rule.clone(),
+            reversible: self.reversible.clone(),
+        }
+    }
+}
+
+impl<O: CodecOps> Clone for DataFixer<O> {
+    fn clone(&self) -> Self {
+        DataFixer {
+            fixes: self.fixes.clone(),
+            schemas: self.schemas.clone(),
+            current_version: self.current_version,
+            mode: self.mode,
+        }
+    }
+}
+
+impl<O: CodecOps> DataFixer<O> {
+    /// Returns the newest version this [`DataFixer`] knows how to upgrade data to.
+    pub fn current_version(&self) -> u32 {
+        self.current_version
+    }
+
+    /// Returns the [`Schema`] that is active in `version`, which is the newest registered schema that is not newer than `version`.
+    pub fn schema(&self, version: u32) -> Option<&Schema> {
+        self.schemas
+            .iter()
+            .rev()
+            .find(|schema| schema.version() <= version)
+            .map(|schema| schema.as_ref())
+    }
+
+    /// Returns how this [`DataFixer`] handles rules that fail to apply.
+    pub fn mode(&self) -> FixMode {
+        self.mode
+    }
+
+    /// Upgrades `value` from `from_version` to `to_version`, applying the rules of every version `v`
+    /// where `from_version < v <= to_version` that are not attached to a [`TypeReference`].
+    ///
+    /// If `from_version` is newer than `to_version`, the rules of every version `v` where
+    /// `to_version < v <= from_version` are reverted instead. Returns an error if one of them is not reversible.
+    pub fn update(
+        &self,
+        ops: &O,
+        value: O::T,
+        from_version: u32,
+        to_version: u32,
+    ) -> CodecResult<O::T> {
+        let mut ctx = Context::new();
+        self.update_with_context(ops, value, None, from_version, to_version, &mut ctx)
+            .map_err(|e| CodecError::new(e, ctx))
+    }
+
+    /// Upgrades `value` from `from_version` to [`DataFixer::current_version`].
+    pub fn update_to_current(&self, ops: &O, value: O::T, from_version: u32) -> CodecResult<O::T> {
+        self.update(ops, value, from_version, self.current_version)
+    }
+
+    /// Upgrades `value`, which is data of the type `reference`, from `from_version` to `to_version`.
+    /// This applies the rules attached to `reference` of every version `v` where `from_version < v <= to_version`.
+    ///
+    /// Like [`DataFixer::update`], this reverts the rules in between if `from_version` is newer than `to_version`.
+    pub fn update_type(
+        &self,
+        ops: &O,
+        reference: &TypeReference,
+        value: O::T,
+        from_version: u32,
+        to_version: u32,
+    ) -> CodecResult<O::T> {
+        let mut ctx = Context::new();
+        self.update_with_context(
+            ops,
+            value,
+            Some(reference),
+            from_version,
+            to_version,
+            &mut ctx,
+        )
+        .map_err(|e| CodecError::new(e, ctx))
+    }
+
+    pub(crate) fn update_with_context(
+        &self,
+        ops: &O,
+        mut value: O::T,
+        reference: Option<&TypeReference>,
+        from_version: u32,
+        to_version: u32,
+        ctx: &mut Context,
+    ) -> DataResult<O::T> {
+        if from_version > to_version {
+            for fix in self.fixes.iter().rev() {
+                if fix.version > to_version
+                    && fix.version <= from_version
+                    && fix.reference.as_ref() == reference
+                {
+                    let Some(rule) = &fix.reversible else {
+                        return Err(DataError::new_custom(&format!(
+                            "can not downgrade data from version {} to {}, a rule of version {} is not reversible",
+                            from_version, to_version, fix.version
+                        )));
+                    };
+                    value = match self.mode {
+                        FixMode::Strict => rule.try_revert_data(ops.clone(), value, ctx)?,
+                        FixMode::Lenient => rule.revert_data(ops.clone(), value),
+                    };
+                }
+            }
+            return Ok(value);
+        }
+        for fix in &self.fixes {
+            if fix.version > from_version
+                && fix.version <= to_version
+                && fix.reference.as_ref() == reference
+            {
+                value = match self.mode {
+                    FixMode::Strict => fix.rule.try_fix_data(ops.clone(), value, ctx)?,
+                    FixMode::Lenient => fix.rule.fix_data(ops.clone(), value),
+                };
+            }
+        }
+        Ok(value)
+    }
+}
+
+/// Controls what a [`DataFixer`] does when a rule fails to apply to a value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FixMode {
+    /// The update stops and returns the error, along with where in the value the rule failed.
+    #[default]
+    Strict,
+    /// Every part of a rule that fails to apply is skipped, leaving that part of the value as is.
+    Lenient,
+}
+
+/// Creates a [`DataFixer`] by registering the rules needed to upgrade data into each version.
+pub struct DataFixerBuilder<O: CodecOps> {
+    fixes: Vec<VersionFix<O>>,
+    schemas: Vec<Schema>,
+    mode: FixMode,
+}
+
+impl<O: CodecOps> Default for DataFixerBuilder<O> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<O: CodecOps> DataFixerBuilder<O> {
+    pub fn new() -> Self {
+        DataFixerBuilder {
+            fixes: Vec::new(),
+            schemas: Vec::new(),
+            mode: FixMode::default(),
+        }
+    }
+
+    /// Sets how the [`DataFixer`] handles rules that fail to apply. Defaults to [`FixMode::Strict`].
+    pub fn mode(mut self, mode: FixMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Registers a rule that upgrades data from the previous version into `version`.
+    /// Registering multiple rules for the same version applies them in the order they were registered.
+    pub fn add_version(mut self, version: u32, rule: impl TypeRewriteRule<O> + 'static) -> Self {
+        self.fixes.push(VersionFix {
+            version,
+            reference: None,
+            rule: Arc::new(rule),
+            reversible: None,
+        });
+        self
+    }
+
+    /// Registers a rule that upgrades data from the previous version into `version`, and that can
+    /// downgrade data from `version` back into the previous version.
+    pub fn add_reversible_version(
+        mut self,
+        version: u32,
+        rule: impl ReversibleRule<O> + 'static,
+    ) -> Self {
+        let rule = Arc::new(rule);
+        self.fixes.push(VersionFix {
+            version,
+            reference: None,
+            rule: rule.clone(),
+            reversible: Some(rule),
+        });
+        self
+    }
+
+    /// Registers a [`Schema`]. If the schema has no parent, the previously registered schema with
+    /// the closest older version becomes its parent.
+    pub fn add_schema(mut self, schema: Schema) -> Self {
+        self.schemas.push(schema);
+        self
+    }
+
+    /// Registers a rule that upgrades data of the type `reference` from the previous version into `version`.
+    pub fn add_type_fix(
+        mut self,
+        version: u32,
+        reference: &TypeReference,
+        rule: impl TypeRewriteRule<O> + 'static,
+    ) -> Self {
+        self.fixes.push(VersionFix {
+            version,
+            reference: Some(reference.clone()),
+            rule: Arc::new(rule),
+            reversible: None,
+        });
+        self
+    }
+
+    /// Registers a reversible rule that upgrades data of the type `reference` from the previous version into `version`.
+    pub fn add_reversible_type_fix(
+        mut self,
+        version: u32,
+        reference: &TypeReference,
+        rule: impl ReversibleRule<O> + 'static,
+    ) -> Self {
+        let rule = Arc::new(rule);
+        self.fixes.push(VersionFix {
+            version,
+            reference: Some(reference.clone()),
+            rule: rule.clone(),
+            reversible: Some(rule),
+        });
+        self
+    }
+
+    /// Builds the [`DataFixer`], checking the registered rules against the registered schemas.
+    ///
+    /// Starting from the oldest [`Schema`] that has a [`Type`](super::Type) for a [`TypeReference`], the rules of each
+    /// newer version are applied to the type of the previous version using [`TypeRewriteRule::try_fix_type`].
+    /// Rules that are not attached to a [`TypeReference`] are checked the same way against [`Schema::value_type`].
+    /// Returns an error naming the version and the reference if one of the rules does not apply, for example because it
+    /// targets a field that does not exist, or if the result is not the type in that version's [`Schema`].
+    pub fn build(mut self) -> DataResult<DataFixer<O>> {
+        self.fixes.sort_by_key(|fix| fix.version);
+        self.schemas.sort_by_key(|schema| schema.version());
+
+        let mut schemas: Vec<Arc<Schema>> = Vec::with_capacity(self.schemas.len());
+        for mut schema in self.schemas {
+            if schema.parent().is_none()
+                && let Some(parent) = schemas.last()
+            {
+                schema.set_parent(parent.clone());
+            }
+            schemas.push(Arc::new(schema));
+        }
+
+        let current_version = self
+            .fixes
+            .iter()
+            .map(|fix| fix.version)
+            .chain(schemas.iter().map(|schema| schema.version()))
+            .max()
+            .unwrap_or(0);
+        let fixer = DataFixer {
+            fixes: self.fixes,
+            schemas,
+            current_version,
+            mode: self.mode,
+        };
+        fixer.check_types()?;
+        Ok(fixer)
+    }
+}
+
+impl<O: CodecOps> DataFixer<O> {
+    fn check_types(&self) -> DataResult<()> {
+        let references = self
+            .schemas
+            .iter()
+            .flat_map(|schema| schema.references())
+            .chain(self.fixes.iter().filter_map(|fix| fix.reference.as_ref()))
+            .collect::<BTreeSet<_>>();
+
+        self.check_chain(None)?;
+        for reference in references {
+            self.check_chain(Some(reference))?;
+        }
+        Ok(())
+    }
+
+    /// Walks the versions in ascending order, starting from the oldest schema that has a type for `reference`.
+    /// The rules of each version are applied to the type of the previous version, and the result is compared
+    /// with the type in that version's schema, if one is registered.
+    fn check_chain(&self, reference: Option<&TypeReference>) -> DataResult<()> {
+        let type_in = |schema: &Schema| match reference {
+            Some(reference) => schema.get_type(reference).ok(),
+            None => schema.value_type(),
+        };
+        let name = reference.map_or("whole values", |reference| reference.name());
+
+        let Some((start, mut ty)) = self
+            .schemas
+            .iter()
+            .find_map(|schema| type_in(schema).map(|ty| (schema.version(), ty)))
+        else {
+            return Ok(());
+        };
+        let versions = self
+            .fixes
+            .iter()
+            .map(|fix| fix.version)
+            .chain(self.schemas.iter().map(|schema| schema.version()))
+            .filter(|version| *version > start)
+            .collect::<BTreeSet<_>>();
+
+        for version in versions {
+            for fix in &self.fixes {
+                if fix.version == version && fix.reference.as_ref() == reference {
+                    ty = fix.rule.try_fix_type(ty).map_err(|e| {
+                        DataError::new_custom(&format!(
+                            "rule of version {} for {} does not apply to its input type: {}",
+                            version, name, e
+                        ))
+                    })?;
+                }
+            }
+            let Some(schema) = self
+                .schemas
+                .iter()
+                .find(|schema| schema.version() == version)
+            else {
+                continue;
+            };
+            let Some(expected) = type_in(schema) else {
+                continue;
+            };
+            if !ty.is_compatible(&expected) {
+                return Err(DataError::new_custom(&format!(
+                    "rules of version {} for {} produce {:?}, but the schema of version {} expects {:?}",
+                    version, name, ty, version, expected
+                )));
+            }
+            ty = expected;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use json::JsonValue;
+
+    use crate::{
+        fixers::{ObjectType, Rules, Schema, Type, TypeReference, TypeRewriteRule},
+        result::DataError,
+        serialization::json::JsonOps,
+    };
+
+    use super::{DataFixerBuilder, FixMode};
+
+    #[test]
+    fn update_through_versions() {
+        let fixer = DataFixerBuilder::new()
+            .add_version(2, Rules::remove_field("y"))
+            .add_version(
+                1,
+                Rules::new_field("y", |ctx| ctx.create_int(20), |_| Type::Int),
+            )
+            .add_version(
+                3,
+                Rules::new_field("z", |ctx| ctx.create_int(30), |_| Type::Int),
+            )
+            .build()
+            .unwrap();
+        assert_eq!(fixer.current_version(), 3);
+
+        let mut object = JsonValue::new_object();
+        let _ = object.insert("x", 10);
+
+        let fixed = fixer
+            .update_to_current(&JsonOps, object.clone(), 0)
+            .unwrap();
+        assert_eq!(fixed, {
+            let mut obj = JsonValue::new_object();
+            let _ = obj.insert("x", 10);
+            let _ = obj.insert("z", 30);
+            obj
+        });
+
+        let fixed = fixer.update(&JsonOps, object, 0, 1).unwrap();
+        assert_eq!(fixed, {
+            let mut obj = JsonValue::new_object();
+            let _ = obj.insert("x", 10);
+            let _ = obj.insert("y", 20);
+            obj
+        });
+    }
+
+    #[test]
+    fn update_to_older_version_fails() {
+        let fixer = DataFixerBuilder::new()
+            .add_version(1, Rules::remove_field("x"))
+            .build()
+            .unwrap();
+        assert!(
+            fixer
+                .update(&JsonOps, JsonValue::new_object(), 1, 0)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn strict_and_lenient_modes() {
+        let object = json::parse(r#"{ "inventory": [ { "id": 1 }, { "count": 2 } ] }"#).unwrap();
+        let rule = || {
+            Rules::new_field("checked", |ctx| ctx.create_int(1), |_| Type::Int)
+                .and_then(Rules::at("inventory[*]", Rules::rename_field("id", "item")))
+        };
+
+        let strict = DataFixerBuilder::new()
+            .add_version(1, rule())
+            .build()
+            .unwrap();
+        let error = strict.update(&JsonOps, object.clone(), 0, 1).unwrap_err();
+        assert!(matches!(error.error(), DataError::KeyNotFoundInMap { .. }));
+        assert_eq!(
+            alloc::format!("{}", error.span()),
+            "Stack trace: $.inventory[1]"
+        );
+
+        let lenient = DataFixerBuilder::new()
+            .mode(FixMode::Lenient)
+            .add_version(1, rule())
+            .build()
+            .unwrap();
+        let fixed = lenient.update(&JsonOps, object, 0, 1).unwrap();
+        assert_eq!(
+            fixed,
+            json::parse(r#"{ "inventory": [ { "item": 1 }, { "count": 2 } ], "checked": 1 }"#)
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn type_fixes_and_schemas() {
+        let player = TypeReference::new("player");
+        let item = TypeReference::new("item");
+
+        let fixer = DataFixerBuilder::new()
+            .add_schema(
+                Schema::new(1)
+                    .register_type(&player, Type::Object(ObjectType::new()))
+                    .register_type(&item, Type::Object(ObjectType::new())),
+            )
+            .add_schema(Schema::new(2).register_type(
+                &item,
+                Type::Object(ObjectType::new().field("count", Type::Int)),
+            ))
+            .add_type_fix(
+                2,
+                &item,
+                Rules::new_field("count", |ctx| ctx.create_int(1), |_| Type::Int),
+            )
+            .build()
+            .unwrap();
+        assert_eq!(fixer.current_version(), 2);
+        assert!(matches!(
+            fixer.schema(5).unwrap().get_type(&player),
+            Ok(Type::Object(_))
+        ));
+        assert!(fixer.schema(0).is_none());
+
+        let fixed = fixer
+            .update_type(&JsonOps, &item, JsonValue::new_object(), 1, 2)
+            .unwrap();
+        assert_eq!(fixed, {
+            let mut obj = JsonValue::new_object();
+            let _ = obj.insert("count", 1);
+            obj
+        });
+
+        let fixed = fixer
+            .update_type(&JsonOps, &player, JsonValue::new_object(), 1, 2)
+            .unwrap();
+        assert_eq!(fixed, JsonValue::new_object());
+    }
+
+    #[test]
+    fn build_checks_rule_types() {
+        let item = TypeReference::new("item");
+        let schemas = || {
+            [
+                Schema::new(1).register_type(
+                    &item,
+                    Type::Object(ObjectType::new().field("color", Type::String)),
+                ),
+                Schema::new(2).register_type(
+                    &item,
+                    Type::Object(
+                        ObjectType::new()
+                            .field("color", Type::String)
+                            .field("count", Type::Int),
+                    ),
+                ),
+            ]
+        };
+        let add_count = || Rules::new_field("count", |ctx| ctx.create_int(1), |_| Type::Int);
+
+        let [v1, v2] = schemas();
+        let fixer = DataFixerBuilder::<JsonOps>::new()
+            .add_schema(v1)
+            .add_schema(v2)
+            .add_type_fix(2, &item, add_count())
+            .build();
+        assert!(fixer.is_ok());
+
+        let [v1, v2] = schemas();
+        let typo = DataFixerBuilder::<JsonOps>::new()
+            .add_schema(v1)
+            .add_schema(v2)
+            .add_type_fix(
+                2,
+                &item,
+                add_count().and_then(Rules::apply_to_field("colour", Rules::identity())),
+            )
+            .build();
+        assert!(typo.is_err());
+
+        let [v1, v2] = schemas();
+        let missing_fix = DataFixerBuilder::<JsonOps>::new()
+            .add_schema(v1)
+            .add_schema(v2)
+            .build();
+        assert!(missing_fix.is_err());
+    }
+
+    #[test]
+    fn build_checks_every_version() {
+        let item = TypeReference::new("item");
+        let object = |fields: &[&str]| {
+            Type::Object(fields.iter().fold(ObjectType::new(), |object, field| {
+                object.field(field, Type::Int)
+            }))
+        };
+        let add =
+            |name: &'static str| Rules::new_field(name, |ctx| ctx.create_int(0), |_| Type::Int);
+
+        // Version 2 adds "b" although its schema doesn't have it, and version 3 removes it again.
+        let error = DataFixerBuilder::<JsonOps>::new()
+            .add_schema(Schema::new(1).register_type(&item, object(&["a"])))
+            .add_schema(Schema::new(2))
+            .add_schema(Schema::new(3))
+            .add_type_fix(2, &item, add("b"))
+            .add_type_fix(3, &item, Rules::remove_field("b"))
+            .build()
+            .err()
+            .unwrap();
+        assert!(alloc::format!("{}", error).starts_with("rules of version 2 for item produce"));
+
+        let error = DataFixerBuilder::<JsonOps>::new()
+            .add_schema(Schema::new(1).register_value_type(object(&["a"])))
+            .add_schema(Schema::new(2).register_value_type(object(&["a", "b"])))
+            .add_version(2, Rules::remove_field("c"))
+            .build()
+            .err()
+            .unwrap();
+        assert!(
+            alloc::format!("{}", error)
+                .starts_with("rule of version 2 for whole values does not apply")
+        );
+
+        let fixer = DataFixerBuilder::<JsonOps>::new()
+            .add_schema(Schema::new(1).register_value_type(object(&["a"])))
+            .add_schema(Schema::new(2).register_value_type(object(&["a", "b"])))
+            .add_version(2, add("b"))
+            .build();
+        assert!(fixer.is_ok());
+    }
+}
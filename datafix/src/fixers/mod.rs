@@ -1,17 +1,49 @@
 use core::marker::PhantomData;
 
+use alloc::boxed::Box;
+
+mod diff;
+pub use diff::*;
+mod fixer;
+pub use fixer::*;
+/// Optics focus on parts of a value, so that rules can be applied to them.
+pub mod optics;
 mod types;
 pub use types::*;
+mod predicates;
+pub use predicates::*;
 mod rules;
 pub use rules::*;
+mod schema;
+pub use schema::*;
 
-use crate::serialization::CodecOps;
+use crate::{
+    result::DataResult,
+    serialization::{CodecOps, Context},
+};
 
+/// A [`TypeRewriteRule`] describes a change to the shape of data, both to the data itself and to the [`Type`] describing it.
 pub trait TypeRewriteRule<O: CodecOps> {
-    fn fix_data(&self, ops: O, value: O::T) -> O::T;
+    /// Applies this rule to a value, returning an error if the value does not have the shape this rule expects.
+    /// `ctx` keeps track of where in the value the rule failed, in the same way as [`Codec::decode`].
+    ///
+    /// [`Codec::decode`]: crate::serialization::Codec::decode
+    fn try_fix_data(&self, ops: O, value: O::T, ctx: &mut Context) -> DataResult<O::T>;
+    /// Applies this rule to a value. Parts of the rule that fail to apply leave the value as is.
+    fn fix_data(&self, ops: O, value: O::T) -> O::T {
+        self.try_fix_data(ops, value.clone(), &mut Context::new())
+            .unwrap_or(value)
+    }
     fn fix_type(&self, ty: Type) -> Type;
+    /// Applies this rule to a type, returning an error if the type does not have the shape this rule expects,
+    /// such as a field the rule targets being missing. A [`DataFixer`] uses this to check its rules when it is built.
+    ///
+    /// [`Type::Unknown`] is accepted by every rule, since nothing can be checked about it.
+    fn try_fix_type(&self, ty: Type) -> DataResult<Type> {
+        Ok(self.fix_type(ty))
+    }
 
-    fn and_then(self, other: impl TypeRewriteRule<O>) -> impl TypeRewriteRule<O>
+    fn and_then<R: TypeRewriteRule<O>>(self, other: R) -> AndThenRule<O, Self, R>
     where
         Self: Sized,
     {
@@ -23,13 +55,53 @@ pub trait TypeRewriteRule<O: CodecOps> {
     }
 }
 
+impl<O: CodecOps, R: TypeRewriteRule<O> + ?Sized> TypeRewriteRule<O> for Box<R> {
+    fn try_fix_data(&self, ops: O, value: O::T, ctx: &mut Context) -> DataResult<O::T> {
+        self.as_ref().try_fix_data(ops, value, ctx)
+    }
+
+    fn fix_data(&self, ops: O, value: O::T) -> O::T {
+        self.as_ref().fix_data(ops, value)
+    }
+
+    fn fix_type(&self, ty: Type) -> Type {
+        self.as_ref().fix_type(ty)
+    }
+
+    fn try_fix_type(&self, ty: Type) -> DataResult<Type> {
+        self.as_ref().try_fix_type(ty)
+    }
+}
+
+/// A [`TypeRewriteRule`] that can also be undone, migrating data back to the shape it had before the rule was applied.
+/// This is what allows a [`DataFixer`] to downgrade data to an older version.
+pub trait ReversibleRule<O: CodecOps>: TypeRewriteRule<O> {
+    /// Reverts this rule on a value, returning an error if the value does not have the shape this rule produces.
+    fn try_revert_data(&self, ops: O, value: O::T, ctx: &mut Context) -> DataResult<O::T>;
+    /// Reverts this rule on a value. Parts of the rule that fail to revert leave the value as is.
+    fn revert_data(&self, ops: O, value: O::T) -> O::T {
+        self.try_revert_data(ops, value.clone(), &mut Context::new())
+            .unwrap_or(value)
+    }
+    fn revert_type(&self, ty: Type) -> Type;
+    /// Reverts this rule on a type, returning an error if the type does not have the shape this rule produces.
+    /// This is what [`TypeRewriteRule::try_fix_type`] checks for a reversed rule.
+    fn try_revert_type(&self, ty: Type) -> DataResult<Type> {
+        Ok(self.revert_type(ty))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use json::JsonValue;
 
     use crate::{
-        fixers::{Type, TypeRewriteRule},
-        serialization::{CodecOps, json::JsonOps},
+        fixers::{
+            ArrayType, DataFixerBuilder, EitherType, MapType, ObjectType, OptionalType,
+            PathSegment, Predicates, RecursiveType, ReversibleRule, Schema, TaggedChoiceType, Type,
+            TypeRewriteRule,
+        },
+        serialization::{CodecOps, Context, json::JsonOps},
     };
 
     use super::Rules;
@@ -100,6 +172,624 @@ mod tests {
         assert_eq!(fixed, JsonValue::new_object())
     }
 
+    #[test]
+    pub fn rename_field_rule() {
+        let mut object = JsonValue::new_object();
+        let _ = object.insert("x", 10);
+        let _ = object.insert("y", 20);
+
+        let rule = Rules::rename_field("x", "z");
+
+        let fixed = rule.fix_data(JsonOps, object.clone());
+        assert_eq!(fixed, {
+            let mut obj = JsonValue::new_object();
+            let _ = obj.insert("y", 20);
+            let _ = obj.insert("z", 10);
+            obj
+        });
+        assert_eq!(rule.revert_data(JsonOps, fixed), object);
+
+        let ty = rule.fix_type(Type::Object(ObjectType::new().field("x", Type::Int)));
+        let Type::Object(ty) = ty else {
+            panic!("expected an object type");
+        };
+        assert!(ty.get("x").is_err());
+        assert!(matches!(ty.get("z"), Ok(Type::Int)));
+
+        let missing = Rules::rename_field("w", "v").fix_data(JsonOps, object.clone());
+        assert_eq!(missing, object);
+
+        let collision = Rules::rename_field("x", "y");
+        assert!(
+            collision
+                .try_fix_data(JsonOps, object.clone(), &mut Context::new())
+                .is_err()
+        );
+        assert_eq!(collision.fix_data(JsonOps, object.clone()), object);
+        let ty = Type::Object(
+            ObjectType::new()
+                .field("x", Type::Int)
+                .field("y", Type::Int),
+        );
+        assert!(collision.try_fix_type(ty.clone()).is_err());
+        let Type::Object(ty) = collision.fix_type(ty) else {
+            panic!("expected an object type");
+        };
+        assert!(matches!(ty.get("x"), Ok(Type::Int)));
+        assert!(matches!(ty.get("y"), Ok(Type::Int)));
+    }
+
+    #[test]
+    pub fn path_rule() {
+        let object = json::parse(
+            r#"{ "settings": { "display": [ { "font_size": 10 }, { "font_size": 12 } ], "name": "x" } }"#,
+        )
+        .unwrap();
+
+        let rule = Rules::at(
+            "settings.display[*]",
+            Rules::rename_field("font_size", "size"),
+        );
+        let fixed = rule.fix_data(JsonOps, object.clone());
+        assert_eq!(
+            fixed,
+            json::parse(
+                r#"{ "settings": { "display": [ { "size": 10 }, { "size": 12 } ], "name": "x" } }"#,
+            )
+            .unwrap()
+        );
+        assert_eq!(rule.revert_data(JsonOps, fixed), object);
+
+        let rule = Rules::at(
+            "settings.display[1]",
+            Rules::new_field("bold", |ctx| ctx.create_int(1), |_| Type::Int),
+        );
+        let fixed = rule.fix_data(JsonOps, object);
+        assert_eq!(
+            fixed,
+            json::parse(
+                r#"{ "settings": { "display": [ { "font_size": 10 }, { "font_size": 12, "bold": 1 } ], "name": "x" } }"#,
+            )
+            .unwrap()
+        );
+
+        let ty = Type::Object(ObjectType::new().field(
+            "display",
+            Type::Array(ArrayType::new(Type::Object(
+                ObjectType::new().field("font_size", Type::Int),
+            ))),
+        ));
+        let ty = Rules::at::<JsonOps, _>("display[*]", Rules::rename_field("font_size", "size"))
+            .fix_type(ty);
+        let Type::Object(ty) = ty else {
+            panic!("expected an object type");
+        };
+        let Ok(Type::Array(display)) = ty.get("display") else {
+            panic!("expected an array type");
+        };
+        let Type::Object(element) = display.ty() else {
+            panic!("expected an object type");
+        };
+        assert!(matches!(element.get("size"), Ok(Type::Int)));
+
+        assert!(Rules::try_at::<JsonOps, _>("settings..name", Rules::remove_field("x")).is_err());
+        assert!(Rules::try_at::<JsonOps, _>("list[x]", Rules::remove_field("x")).is_err());
+        let dotted = Rules::at_segments(
+            [PathSegment::Field("a.b".into())],
+            Rules::rename_field("x", "y"),
+        );
+        assert_eq!(
+            dotted.fix_data(JsonOps, json::parse(r#"{ "a.b": { "x": 1 } }"#).unwrap()),
+            json::parse(r#"{ "a.b": { "y": 1 } }"#).unwrap()
+        );
+    }
+
+    #[test]
+    pub fn each_element_rule() {
+        let object =
+            json::parse(r#"{ "inventory": [ { "id": "stone" }, { "id": "dirt", "count": 3 } ] }"#)
+                .unwrap();
+
+        let rule = Rules::apply_to_field(
+            "inventory",
+            Rules::each_element(Rules::rename_field("id", "item")),
+        );
+        let fixed = rule.fix_data(JsonOps, object);
+        assert_eq!(
+            fixed,
+            json::parse(
+                r#"{ "inventory": [ { "item": "stone" }, { "item": "dirt", "count": 3 } ] }"#
+            )
+            .unwrap()
+        );
+
+        let ty = Rules::each_element::<JsonOps, _>(Rules::rename_field("id", "item")).fix_type(
+            Type::Array(ArrayType::new(Type::Object(
+                ObjectType::new().field("id", Type::String),
+            ))),
+        );
+        let Type::Array(array) = ty else {
+            panic!("expected an array type");
+        };
+        let Type::Object(element) = array.ty() else {
+            panic!("expected an object type");
+        };
+        assert!(matches!(element.get("item"), Ok(Type::String)));
+        assert!(element.get("id").is_err());
+    }
+
+    #[test]
+    pub fn convert_field_rule() {
+        let mut object = JsonValue::new_object();
+        let _ = object.insert("timeout", 30);
+
+        let rule = Rules::convert_field("timeout", Type::Int, Type::String, |value| {
+            Ok(value.create_string(&alloc::format!("{}s", value.as_int()?)))
+        });
+
+        let fixed = rule
+            .try_fix_data(JsonOps, object.clone(), &mut Context::new())
+            .unwrap();
+        assert_eq!(fixed, {
+            let mut obj = JsonValue::new_object();
+            let _ = obj.insert("timeout", "30s");
+            obj
+        });
+
+        let ty = rule.fix_type(Type::Object(ObjectType::new().field("timeout", Type::Int)));
+        assert_eq!(
+            ty,
+            Type::Object(ObjectType::new().field("timeout", Type::String))
+        );
+
+        assert!(
+            rule.try_fix_data(JsonOps, fixed.clone(), &mut Context::new())
+                .is_err()
+        );
+        assert_eq!(rule.fix_data(JsonOps, fixed.clone()), fixed);
+        assert!(
+            JsonOps
+                .try_repair(
+                    {
+                        let mut obj = JsonValue::new_object();
+                        let _ = obj.insert("nested", fixed);
+                        obj
+                    },
+                    Rules::apply_to_field("nested", rule),
+                )
+                .is_err()
+        );
+    }
+
+    #[test]
+    pub fn conditional_rules() {
+        let click = json::parse(r#"{ "event": "click", "x": 1 }"#).unwrap();
+        let key = json::parse(r#"{ "event": "key", "value": "a" }"#).unwrap();
+
+        let rule = Rules::when(
+            Predicates::field_equals("event", JsonOps.create_string("click")),
+            Rules::rename_field("x", "pos_x"),
+        );
+        assert_eq!(
+            rule.fix_data(JsonOps, click.clone()),
+            json::parse(r#"{ "event": "click", "pos_x": 1 }"#).unwrap()
+        );
+        assert_eq!(rule.fix_data(JsonOps, key.clone()), key);
+
+        let rule = Rules::if_else(
+            Predicates::has_field("value"),
+            Rules::new_field("modifiers", |ctx| ctx.create_int(0), |_| Type::Int),
+            Rules::remove_field("x"),
+        );
+        assert_eq!(
+            rule.fix_data(JsonOps, key),
+            json::parse(r#"{ "event": "key", "value": "a", "modifiers": 0 }"#).unwrap()
+        );
+        assert_eq!(
+            rule.fix_data(JsonOps, click),
+            json::parse(r#"{ "event": "click" }"#).unwrap()
+        );
+
+        let ty = Type::Object(
+            ObjectType::new()
+                .field("event", Type::String)
+                .field("x", Type::Int),
+        );
+        assert_eq!(
+            rule.fix_type(ty.clone()),
+            Type::Either(EitherType::new(
+                Type::Object(
+                    ObjectType::new()
+                        .field("event", Type::String)
+                        .field("x", Type::Int)
+                        .field("modifiers", Type::Int)
+                ),
+                Type::Object(ObjectType::new().field("event", Type::String)),
+            ))
+        );
+        let same = Rules::if_else(
+            Predicates::has_field::<JsonOps>("x"),
+            Rules::remove_field("x"),
+            Rules::remove_field("x"),
+        );
+        assert_eq!(
+            same.fix_type(ty),
+            Type::Object(ObjectType::new().field("event", Type::String))
+        );
+    }
+
+    #[test]
+    pub fn dispatch_rules() {
+        let rule = Rules::dispatch("event", [("click", Rules::rename_field("x", "pos_x"))])
+            .variant("key", Rules::remove_field("repeat"));
+
+        let click = json::parse(r#"{ "event": "click", "x": 1 }"#).unwrap();
+        let key = json::parse(r#"{ "event": "key", "value": "a", "repeat": 0 }"#).unwrap();
+        let load = json::parse(r#"{ "event": "page_load" }"#).unwrap();
+        assert_eq!(
+            rule.fix_data(JsonOps, click),
+            json::parse(r#"{ "event": "click", "pos_x": 1 }"#).unwrap()
+        );
+        assert_eq!(
+            rule.fix_data(JsonOps, key),
+            json::parse(r#"{ "event": "key", "value": "a" }"#).unwrap()
+        );
+        assert_eq!(rule.fix_data(JsonOps, load.clone()), load);
+        assert!(
+            rule.try_fix_data(JsonOps, json::parse("{}").unwrap(), &mut Context::new())
+                .is_err()
+        );
+
+        let ty = Type::TaggedChoice(
+            TaggedChoiceType::new("event")
+                .variant(
+                    "click",
+                    Type::Object(ObjectType::new().field("x", Type::Int)),
+                )
+                .variant("page_load", Type::Object(ObjectType::new())),
+        );
+        assert_eq!(
+            rule.fix_type(ty.clone()),
+            Type::TaggedChoice(
+                TaggedChoiceType::new("event")
+                    .variant(
+                        "click",
+                        Type::Object(ObjectType::new().field("pos_x", Type::Int))
+                    )
+                    .variant("page_load", Type::Object(ObjectType::new())),
+            )
+        );
+
+        // `key` has a rule but is not part of the tagged choice.
+        assert!(rule.try_fix_type(ty.clone()).is_err());
+        let Type::TaggedChoice(choice) = ty.clone() else {
+            unreachable!()
+        };
+        let with_key = Type::TaggedChoice(choice.variant(
+            "key",
+            Type::Object(ObjectType::new().field("repeat", Type::Int)),
+        ));
+        assert!(rule.try_fix_type(with_key).is_ok());
+
+        let rename = Rules::rename_variant("event", "page_load", "load");
+        let renamed = rename.fix_data(JsonOps, load.clone());
+        assert_eq!(renamed, json::parse(r#"{ "event": "load" }"#).unwrap());
+        assert_eq!(rename.revert_data(JsonOps, renamed), load);
+        assert_eq!(
+            TypeRewriteRule::<JsonOps>::fix_type(&rename, ty.clone()),
+            Type::TaggedChoice(
+                TaggedChoiceType::new("event")
+                    .variant(
+                        "click",
+                        Type::Object(ObjectType::new().field("x", Type::Int))
+                    )
+                    .variant("load", Type::Object(ObjectType::new())),
+            )
+        );
+        assert!(TypeRewriteRule::<JsonOps>::try_fix_type(&rename, ty.clone()).is_ok());
+        let renamed = TypeRewriteRule::<JsonOps>::fix_type(&rename, ty.clone());
+        // Once renamed, there is no `page_load` variant left to rename.
+        assert!(TypeRewriteRule::<JsonOps>::try_fix_type(&rename, renamed.clone()).is_err());
+        assert!(
+            Rules::rename_variant::<JsonOps>("event", "click", "page_load")
+                .try_fix_type(ty.clone())
+                .is_err()
+        );
+        assert_eq!(rename.try_revert_type(renamed).unwrap(), ty);
+        assert!(TypeRewriteRule::<JsonOps>::try_fix_type(&rename, Type::Int).is_err());
+    }
+
+    #[test]
+    pub fn path_rule_through_optional_types() {
+        let node = RecursiveType::new(
+            "node",
+            Type::Object(
+                ObjectType::new()
+                    .field("value", Type::Int)
+                    .field("visible", Type::Boolean)
+                    .field(
+                        "next",
+                        Type::Optional(OptionalType::new(Type::Named("node".into()))),
+                    )
+                    .field("tags", Type::Map(MapType::new(Type::Unit))),
+            ),
+        );
+        let ty = Type::Object(ObjectType::new().field(
+            "head",
+            Type::Optional(OptionalType::new(Type::Recursive(node))),
+        ));
+
+        let fixed = Rules::at::<JsonOps, _>("head", Rules::remove_field("visible")).fix_type(ty);
+        let Type::Object(fixed) = fixed else {
+            panic!("expected an object type");
+        };
+        let Ok(Type::Optional(head)) = fixed.get("head") else {
+            panic!("expected an optional type");
+        };
+        let Type::Recursive(head) = head.ty() else {
+            panic!("expected a recursive type");
+        };
+        let Type::Object(head) = head.ty() else {
+            panic!("expected an object type");
+        };
+        assert!(head.get("visible").is_err());
+        assert!(matches!(head.get("next"), Ok(Type::Optional(_))));
+    }
+
+    #[test]
+    pub fn nest_and_flatten_fields() {
+        let value = json::parse(r#"{ "name": "a", "x": 1, "y": 2, "z": 3 }"#).unwrap();
+        let nested = json::parse(r#"{ "name": "a", "pos": { "x": 1, "y": 2, "z": 3 } }"#).unwrap();
+
+        let nest = Rules::nest_fields(["x", "y", "z"], "pos");
+        assert_eq!(nest.fix_data(JsonOps, value.clone()), nested);
+        assert_eq!(nest.revert_data(JsonOps, nested.clone()), value);
+
+        let flatten = Rules::flatten_field("pos", "pos_");
+        let flattened =
+            json::parse(r#"{ "name": "a", "pos_x": 1, "pos_y": 2, "pos_z": 3 }"#).unwrap();
+        assert_eq!(flatten.fix_data(JsonOps, nested.clone()), flattened);
+        assert_eq!(flatten.revert_data(JsonOps, flattened), nested);
+        let unnamed = json::parse(r#"{ "name": "a" }"#).unwrap();
+        assert_eq!(flatten.revert_data(JsonOps, unnamed.clone()), unnamed);
+        let collision = json::parse(r#"{ "pos_x": 0, "pos": { "x": 1 } }"#).unwrap();
+        assert!(
+            flatten
+                .try_fix_data(JsonOps, collision, &mut Context::new())
+                .is_err()
+        );
+
+        // An optional field that is absent leaves the value as is, even in strict mode.
+        let fixer = DataFixerBuilder::new()
+            .add_schema(Schema::new(1).register_value_type(Type::Object(
+                ObjectType::new().field("name", Type::String).field(
+                    "pos",
+                    Type::Optional(OptionalType::new(Type::Object(
+                        ObjectType::new().field("x", Type::Int),
+                    ))),
+                ),
+            )))
+            .add_schema(
+                Schema::new(2).register_value_type(Type::Object(
+                    ObjectType::new()
+                        .field("name", Type::String)
+                        .field("pos_x", Type::Optional(OptionalType::new(Type::Int))),
+                )),
+            )
+            .add_version(2, Rules::flatten_field("pos", "pos_"))
+            .build()
+            .unwrap();
+        let unpositioned = json::parse(r#"{ "name": "a" }"#).unwrap();
+        assert_eq!(
+            fixer
+                .update_to_current(&JsonOps, unpositioned.clone(), 1)
+                .unwrap(),
+            unpositioned
+        );
+
+        let ty = Type::Object(
+            ObjectType::new()
+                .field("name", Type::String)
+                .field("x", Type::Int)
+                .field("y", Type::Int)
+                .field("z", Type::Int),
+        );
+        let nested_ty = TypeRewriteRule::<JsonOps>::fix_type(&nest, ty.clone());
+        assert_eq!(
+            nested_ty,
+            Type::Object(
+                ObjectType::new().field("name", Type::String).field(
+                    "pos",
+                    Type::Object(
+                        ObjectType::new()
+                            .field("x", Type::Int)
+                            .field("y", Type::Int)
+                            .field("z", Type::Int)
+                    )
+                )
+            )
+        );
+        assert_eq!(
+            ReversibleRule::<JsonOps>::revert_type(&nest, nested_ty.clone()),
+            ty
+        );
+        assert_eq!(
+            TypeRewriteRule::<JsonOps>::fix_type(
+                &Rules::flatten_field("pos", ""),
+                nested_ty.clone()
+            ),
+            ty
+        );
+        // Missing fields are skipped on the type side too, like they are in the data.
+        let partial = Type::Object(ObjectType::new().field("x", Type::Int));
+        assert_eq!(
+            TypeRewriteRule::<JsonOps>::try_fix_type(&nest, partial.clone()).unwrap(),
+            Type::Object(
+                ObjectType::new()
+                    .field("pos", Type::Object(ObjectType::new().field("x", Type::Int)))
+            )
+        );
+        assert_eq!(
+            TypeRewriteRule::<JsonOps>::try_fix_type(&flatten, partial.clone()).unwrap(),
+            partial
+        );
+        let colliding = Type::Object(
+            ObjectType::new()
+                .field("pos_x", Type::Int)
+                .field("pos", Type::Object(ObjectType::new().field("x", Type::Int))),
+        );
+        assert!(TypeRewriteRule::<JsonOps>::try_fix_type(&flatten, colliding).is_err());
+
+        let flat_ty = Type::Object(
+            ObjectType::new()
+                .field("name", Type::String)
+                .field("pos_x", Type::Int),
+        );
+        assert_eq!(
+            flatten.try_revert_type(flat_ty.clone()).unwrap(),
+            Type::Object(
+                ObjectType::new()
+                    .field("name", Type::String)
+                    .field("pos", Type::Object(ObjectType::new().field("x", Type::Int)))
+            )
+        );
+        assert!(flatten.try_revert_type(nested_ty).is_err());
+    }
+
+    #[test]
+    pub fn list_and_map_conversion() {
+        let list =
+            json::parse(r#"[ { "key": "a", "value": 1 }, { "key": "b", "value": 2 } ]"#).unwrap();
+        let map = json::parse(r#"{ "a": 1, "b": 2 }"#).unwrap();
+
+        let to_map = Rules::list_to_map("key", "value");
+        assert_eq!(to_map.fix_data(JsonOps, list.clone()), map);
+        assert_eq!(to_map.revert_data(JsonOps, map.clone()), list);
+        assert_eq!(
+            Rules::map_to_list("key", "value").fix_data(JsonOps, map),
+            list
+        );
+
+        let duplicate =
+            json::parse(r#"[ { "key": "a", "value": 1 }, { "key": "a", "value": 2 } ]"#).unwrap();
+        let error = JsonOps.try_repair(duplicate, to_map).unwrap_err();
+        assert_eq!(alloc::format!("{}", error.span()), "Stack trace: $[1].key");
+
+        let list_ty = Type::Array(ArrayType::new(Type::Object(
+            ObjectType::new()
+                .field("key", Type::String)
+                .field("value", Type::Int),
+        )));
+        let map_ty = Type::Map(MapType::new(Type::Int));
+        let to_map = Rules::list_to_map::<JsonOps>("key", "value");
+        assert_eq!(to_map.try_fix_type(list_ty.clone()).unwrap(), map_ty);
+        assert_eq!(to_map.revert_type(map_ty), list_ty);
+        assert!(to_map.try_fix_type(Type::Int).is_err());
+    }
+
+    #[test]
+    pub fn map_key_and_value_rules() {
+        let blocks =
+            json::parse(r#"{ "stone": { "count": 1 }, "minecraft:dirt": { "count": 2 } }"#)
+                .unwrap();
+
+        let namespace = Rules::rename_map_keys(|key: &str| {
+            (!key.contains(':')).then(|| alloc::format!("minecraft:{key}"))
+        });
+        assert_eq!(
+            namespace.fix_data(JsonOps, blocks.clone()),
+            json::parse(
+                r#"{ "minecraft:stone": { "count": 1 }, "minecraft:dirt": { "count": 2 } }"#
+            )
+            .unwrap()
+        );
+
+        let collide = Rules::rename_map_keys(|_: &str| Some("same".into()));
+        let error = JsonOps.try_repair(blocks.clone(), collide).unwrap_err();
+        assert_eq!(alloc::format!("{}", error.span()), "Stack trace: $.same");
+
+        let rename_count = Rules::map_values(Rules::rename_field("count", "amount"));
+        let renamed = rename_count.fix_data(JsonOps, blocks.clone());
+        assert_eq!(
+            renamed,
+            json::parse(r#"{ "stone": { "amount": 1 }, "minecraft:dirt": { "amount": 2 } }"#)
+                .unwrap()
+        );
+        assert_eq!(rename_count.revert_data(JsonOps, renamed), blocks);
+
+        let ty = Type::Map(MapType::new(Type::Object(
+            ObjectType::new().field("count", Type::Int),
+        )));
+        assert_eq!(
+            rename_count.try_fix_type(ty).unwrap(),
+            Type::Map(MapType::new(Type::Object(
+                ObjectType::new().field("amount", Type::Int)
+            )))
+        );
+        assert!(rename_count.try_fix_type(Type::Int).is_err());
+    }
+
+    #[test]
+    pub fn path_parsing() {
+        use super::rules::PathSegment;
+
+        assert_eq!(
+            PathSegment::parse("a.b[2][*].c").unwrap(),
+            [
+                PathSegment::Field("a".into()),
+                PathSegment::Field("b".into()),
+                PathSegment::Index(2),
+                PathSegment::Each,
+                PathSegment::Field("c".into()),
+            ]
+        );
+        assert_eq!(
+            PathSegment::parse("[*].a").unwrap(),
+            [PathSegment::Each, PathSegment::Field("a".into())]
+        );
+        assert!(PathSegment::parse("a..b").is_err());
+        assert!(PathSegment::parse("a[x]").is_err());
+        assert!(PathSegment::parse("a[1").is_err());
+    }
+
+    #[test]
+    pub fn reversible_rules() {
+        let mut object = JsonValue::new_object();
+        let _ = object.insert("x", 10);
+
+        let rule = Rules::new_field("y", |ctx| ctx.create_int(20), |_| Type::Int).and_then(
+            Rules::remove_field_with_default("x", |ctx| ctx.create_int(5), |_| Type::Int),
+        );
+
+        let fixed = rule.fix_data(JsonOps, object);
+        assert_eq!(fixed, {
+            let mut obj = JsonValue::new_object();
+            let _ = obj.insert("y", 20);
+            obj
+        });
+
+        let reverted = rule.revert_data(JsonOps, fixed);
+        assert_eq!(reverted, {
+            let mut obj = JsonValue::new_object();
+            let _ = obj.insert("x", 5);
+            obj
+        });
+
+        // Reversed rules are checked in the direction they are applied.
+        let x = Type::Object(ObjectType::new().field("x", Type::Int));
+        assert_eq!(
+            rule.try_fix_type(x.clone()).unwrap(),
+            Type::Object(ObjectType::new().field("y", Type::Int))
+        );
+        assert!(rule.try_fix_type(Type::Object(ObjectType::new())).is_err());
+        assert_eq!(
+            rule.try_revert_type(Type::Object(ObjectType::new().field("y", Type::Int)))
+                .unwrap(),
+            x
+        );
+        assert!(rule.try_revert_type(x).is_err());
+    }
+
     #[test]
     pub fn nested_field_application() {
         let mut nested = JsonValue::new_object();
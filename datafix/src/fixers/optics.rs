@@ -0,0 +1,300 @@
+use core::marker::PhantomData;
+
+use alloc::{string::ToString, vec::Vec};
+
+use crate::{
+    result::{DataError, DataResult},
+    serialization::{CodecOps, Context, Dynamic},
+};
+
+use super::{AtPathRule, TypeRewriteRule, rules::PathSegment};
+
+/// Returns every focus of `path` in `value`.
+fn collect<O: CodecOps>(path: &[PathSegment], ops: &O, value: &O::T) -> DataResult<Vec<O::T>> {
+    let mut foci = Vec::new();
+    PathSegment::collect(path, ops, value, &mut Context::new(), &mut foci)?;
+    Ok(foci)
+}
+
+/// A [`Lens`] focuses on exactly one part of a value, such as a field of an object.
+/// Using a [`Lens`] on a value without that part is an error.
+pub struct Lens<O: CodecOps> {
+    path: Vec<PathSegment>,
+    _phantom: PhantomData<fn() -> O>,
+}
+
+/// A [`Prism`] focuses on a part of a value that may not be there, such as one variant of a tagged choice.
+/// Values of another variant, values without the tag and values that are not maps at all have no focus,
+/// and are left as they are.
+pub struct Prism<O: CodecOps> {
+    path: Vec<PathSegment>,
+    _phantom: PhantomData<fn() -> O>,
+}
+
+/// A [`Traversal`] focuses on any number of parts of a value, such as every element of a list.
+pub struct Traversal<O: CodecOps> {
+    path: Vec<PathSegment>,
+    _phantom: PhantomData<fn() -> O>,
+}
+
+impl<O: CodecOps> Lens<O> {
+    /// Focuses on the field `name` of an object.
+    pub fn field(name: &str) -> Self {
+        Lens {
+            path: [PathSegment::Field(name.to_string())].into(),
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Focuses on the element at `index` of a list.
+    pub fn index(index: usize) -> Self {
+        Lens {
+            path: [PathSegment::Index(index)].into(),
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Returns the value this lens focuses on.
+    pub fn get(&self, ops: &O, value: &O::T) -> DataResult<O::T> {
+        collect(&self.path, ops, value)?
+            .pop()
+            .ok_or_else(|| DataError::new_custom("lens has no focus"))
+    }
+}
+
+impl<O: CodecOps> Prism<O> {
+    /// Focuses on values whose string field `tag` is `name`, like the rules of [`Rules::dispatch`].
+    /// Unlike [`Rules::dispatch`], values without the tag are not an error, they just have no focus.
+    ///
+    /// [`Rules::dispatch`]: super::Rules::dispatch
+    pub fn variant(tag: &str, name: &str) -> Self {
+        Prism {
+            path: [PathSegment::Variant {
+                tag: tag.to_string(),
+                name: name.to_string(),
+            }]
+            .into(),
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Returns the value this prism focuses on, or `None` if the value is another variant.
+    pub fn get(&self, ops: &O, value: &O::T) -> DataResult<Option<O::T>> {
+        Ok(collect(&self.path, ops, value)?.pop())
+    }
+}
+
+impl<O: CodecOps> Traversal<O> {
+    /// Focuses on every element of a list.
+    pub fn each() -> Self {
+        Traversal {
+            path: [PathSegment::Each].into(),
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Returns every value this traversal focuses on.
+    pub fn get(&self, ops: &O, value: &O::T) -> DataResult<Vec<O::T>> {
+        collect(&self.path, ops, value)
+    }
+}
+
+macro_rules! impl_optic {
+    ($($optic:ident),*) => {
+        $(impl<O: CodecOps> $optic<O> {
+            /// Replaces every focus of this optic in `value` with `new_value`.
+            pub fn set(&self, ops: &O, value: O::T, new_value: O::T) -> DataResult<O::T> {
+                self.modify(ops, value, |_| Ok(Dynamic::new(new_value.clone(), ops.clone())))
+            }
+
+            /// Replaces every focus of this optic in `value` with the result of `f`.
+            pub fn modify(
+                &self,
+                ops: &O,
+                mut value: O::T,
+                f: impl Fn(&Dynamic<O>) -> DataResult<Dynamic<O>>,
+            ) -> DataResult<O::T> {
+                PathSegment::walk_data(&self.path, ops, &mut value, &mut Context::new(), true, &|ops, focus, _| {
+                    Ok(f(&Dynamic::new(focus, ops))?.into_inner())
+                })?;
+                Ok(value)
+            }
+
+            /// Returns a rule that applies `rule` to every focus of this optic, in the same way as [`Rules::at`].
+            /// The result is reversible if the inner rule is.
+            ///
+            /// [`Rules::at`]: super::Rules::at
+            pub fn lift<R: TypeRewriteRule<O>>(self, rule: R) -> AtPathRule<O, R> {
+                AtPathRule {
+                    path: self.path,
+                    rule,
+                    _phantom: PhantomData,
+                }
+            }
+        })*
+    };
+}
+
+impl_optic!(Lens, Prism, Traversal);
+
+/// Composes two optics, focusing on the focus of `other` within the focus of `self`.
+///
+/// Composing two lenses gives a [`Lens`]. Composing with a [`Prism`] gives a [`Prism`], unless a [`Traversal`]
+/// is involved, in which case the result is a [`Traversal`].
+pub trait Compose<Rhs> {
+    type Output;
+
+    fn compose(self, other: Rhs) -> Self::Output;
+}
+
+macro_rules! impl_compose {
+    ($($left:ident + $right:ident => $output:ident),* $(,)?) => {
+        $(impl<O: CodecOps> Compose<$right<O>> for $left<O> {
+            type Output = $output<O>;
+
+            fn compose(mut self, other: $right<O>) -> $output<O> {
+                self.path.extend(other.path);
+                $output {
+                    path: self.path,
+                    _phantom: PhantomData,
+                }
+            }
+        })*
+    };
+}
+
+impl_compose! {
+    Lens + Lens => Lens,
+    Lens + Prism => Prism,
+    Lens + Traversal => Traversal,
+    Prism + Lens => Prism,
+    Prism + Prism => Prism,
+    Prism + Traversal => Traversal,
+    Traversal + Lens => Traversal,
+    Traversal + Prism => Traversal,
+    Traversal + Traversal => Traversal,
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        fixers::{ObjectType, ReversibleRule, Rules, TaggedChoiceType, Type, TypeRewriteRule},
+        serialization::{CodecOps, json::JsonOps},
+    };
+
+    use super::{Compose, Lens, Prism, Traversal};
+
+    #[test]
+    fn get_set_modify() {
+        let value = json::parse(
+            r#"{ "events": [ { "event": "click", "x": 1 }, { "event": "key" }, { "event": "click", "x": 3 } ] }"#,
+        )
+        .unwrap();
+
+        let clicks = Lens::<JsonOps>::field("events")
+            .compose(Traversal::each())
+            .compose(Prism::variant("event", "click"));
+        let xs = clicks.compose(Lens::field("x"));
+        assert_eq!(
+            xs.get(&JsonOps, &value).unwrap(),
+            [JsonOps.create_int(&1), JsonOps.create_int(&3)]
+        );
+
+        let doubled = xs
+            .modify(&JsonOps, value.clone(), |x| {
+                Ok(x.create_int(x.as_int()? * 2))
+            })
+            .unwrap();
+        assert_eq!(
+            doubled,
+            json::parse(
+                r#"{ "events": [ { "event": "click", "x": 2 }, { "event": "key" }, { "event": "click", "x": 6 } ] }"#,
+            )
+            .unwrap()
+        );
+
+        let first = Lens::<JsonOps>::field("events").compose(Lens::index(1));
+        assert_eq!(
+            first.get(&JsonOps, &value).unwrap(),
+            json::parse(r#"{ "event": "key" }"#).unwrap()
+        );
+        let key = Lens::<JsonOps>::index(1).compose(Prism::variant("event", "key"));
+        let events = Lens::<JsonOps>::field("events")
+            .get(&JsonOps, &value)
+            .unwrap();
+        assert!(key.get(&JsonOps, &events).unwrap().is_some());
+        assert!(
+            Prism::<JsonOps>::variant("event", "key")
+                .get(&JsonOps, &json::parse(r#"{ "event": "click" }"#).unwrap())
+                .unwrap()
+                .is_none()
+        );
+
+        // Values without the tag, including values that are not maps, have no focus.
+        let clicks = Prism::<JsonOps>::variant("event", "click");
+        for untagged in [
+            JsonOps.create_int(&1),
+            json::parse(r#"{ "x": 1 }"#).unwrap(),
+        ] {
+            assert!(clicks.get(&JsonOps, &untagged).unwrap().is_none());
+            assert_eq!(
+                clicks
+                    .set(&JsonOps, untagged.clone(), JsonOps.create_int(&2))
+                    .unwrap(),
+                untagged
+            );
+        }
+
+        let replaced = first
+            .set(&JsonOps, value.clone(), JsonOps.create_int(&0))
+            .unwrap();
+        assert_eq!(
+            replaced,
+            json::parse(
+                r#"{ "events": [ { "event": "click", "x": 1 }, 0, { "event": "click", "x": 3 } ] }"#,
+            )
+            .unwrap()
+        );
+        assert!(
+            Lens::<JsonOps>::field("missing")
+                .get(&JsonOps, &value)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn lifted_rules() {
+        let value = json::parse(r#"{ "event": "click", "pos": { "x": 1 } }"#).unwrap();
+        let rule = Prism::<JsonOps>::variant("event", "click")
+            .compose(Lens::field("pos"))
+            .lift(Rules::rename_field("x", "left"));
+
+        let fixed = rule.fix_data(JsonOps, value.clone());
+        assert_eq!(
+            fixed,
+            json::parse(r#"{ "event": "click", "pos": { "left": 1 } }"#).unwrap()
+        );
+        assert_eq!(rule.revert_data(JsonOps, fixed), value);
+
+        let ty = Type::TaggedChoice(
+            TaggedChoiceType::new("event").variant(
+                "click",
+                Type::Object(
+                    ObjectType::new()
+                        .field("pos", Type::Object(ObjectType::new().field("x", Type::Int))),
+                ),
+            ),
+        );
+        assert_eq!(
+            rule.try_fix_type(ty).unwrap(),
+            Type::TaggedChoice(TaggedChoiceType::new("event").variant(
+                "click",
+                Type::Object(ObjectType::new().field(
+                    "pos",
+                    Type::Object(ObjectType::new().field("left", Type::Int))
+                )),
+            ))
+        );
+        assert!(rule.try_fix_type(Type::Int).is_err());
+    }
+}
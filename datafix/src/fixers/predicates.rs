@@ -0,0 +1,45 @@
+use alloc::string::ToString;
+
+use crate::serialization::{CodecOps, Dynamic, MapView};
+
+/// This type provides associated methods for creating predicates used by conditional rules such as [`Rules::when`].
+///
+/// [`Rules::when`]: super::Rules::when
+pub struct Predicates;
+
+impl Predicates {
+    /// Matches maps that contain the field `field_name`.
+    pub fn has_field<O: CodecOps>(field_name: &str) -> impl Fn(&Dynamic<O>) -> bool + use<O> {
+        let field_name = field_name.to_string();
+        move |value| {
+            value
+                .ops()
+                .get_map(value.value())
+                .is_ok_and(|map| map.get(&field_name).is_ok())
+        }
+    }
+
+    /// Matches maps where the field `field_name` is equal to `expected`.
+    pub fn field_equals<O: CodecOps>(
+        field_name: &str,
+        expected: O::T,
+    ) -> impl Fn(&Dynamic<O>) -> bool + use<O>
+    where
+        O::T: PartialEq,
+    {
+        let field_name = field_name.to_string();
+        move |value| {
+            value
+                .ops()
+                .get_map(value.value())
+                .is_ok_and(|map| map.get(&field_name).is_ok_and(|field| *field == expected))
+        }
+    }
+
+    /// Matches values that do not match `predicate`.
+    pub fn not<O: CodecOps>(
+        predicate: impl Fn(&Dynamic<O>) -> bool,
+    ) -> impl Fn(&Dynamic<O>) -> bool {
+        move |value| !predicate(value)
+    }
+}
@@ -1,10 +1,22 @@
 use core::marker::PhantomData;
 
-use alloc::string::{String, ToString};
+use alloc::{
+    boxed::Box,
+    collections::btree_map::BTreeMap,
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
 
-use crate::serialization::{CodecOps, Dynamic, MapView, MapViewMut};
+use crate::{
+    result::{DataError, DataResult},
+    serialization::{CodecOps, Context, Dynamic, ListView, ListViewMut, MapView, MapViewMut},
+};
 
-use super::{Type, TypeRewriteRule};
+use super::{
+    ArrayType, EitherType, MapType, ObjectType, OptionalType, RecursiveType, ReversibleRule, Type,
+    TypeRewriteRule,
+};
 
 pub struct Rules;
 
@@ -13,7 +25,7 @@ impl Rules {
         field_name: &str,
         value_function: F,
         type_function: G,
-    ) -> impl TypeRewriteRule<O> {
+    ) -> impl ReversibleRule<O> + use<O, F, G> {
         NewFieldRule {
             field_name: field_name.to_string(),
             value_function,
@@ -22,23 +34,276 @@ impl Rules {
         }
     }
 
-    pub fn remove_field<O: CodecOps>(field_name: &str) -> impl TypeRewriteRule<O> {
+    /// Removes a field. Fails if the field is not present.
+    ///
+    /// This rule can't be reversed: rules only see the value they are migrating, so the removed value has
+    /// nowhere to be kept for a later downgrade. Use [`Rules::remove_field_with_default`] to recreate the field
+    /// when reverting, or keep the value around under another name with [`Rules::rename_field`].
+    pub fn remove_field<O: CodecOps>(field_name: &str) -> impl TypeRewriteRule<O> + use<O> {
         RemoveFieldRule {
             field_name: field_name.to_string(),
             _phantom: PhantomData,
         }
     }
 
-    pub fn apply_to_field<O: CodecOps>(
+    /// Removes a field. Since the removed value is lost, reverting this rule creates the field again
+    /// using the provided functions, in the same way as [`Rules::new_field`].
+    pub fn remove_field_with_default<
+        O: CodecOps,
+        F: Fn(&Dynamic<O>) -> Dynamic<O>,
+        G: Fn(&Type) -> Type,
+    >(
         field_name: &str,
-        rule: impl TypeRewriteRule<O>,
-    ) -> impl TypeRewriteRule<O> {
+        value_function: F,
+        type_function: G,
+    ) -> impl ReversibleRule<O> + use<O, F, G> {
+        Rules::reverse(Rules::new_field(field_name, value_function, type_function))
+    }
+
+    /// Moves the value of a field to a new key. Fails if the field is not present or the new key is already taken.
+    pub fn rename_field<O: CodecOps>(
+        old_name: &str,
+        new_name: &str,
+    ) -> impl ReversibleRule<O> + use<O> {
+        RenameFieldRule {
+            old_name: old_name.to_string(),
+            new_name: new_name.to_string(),
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Changes the representation of a field from the type `from` to the type `to`, converting its value using `f`.
+    ///
+    /// Fails if the field is missing or `f` fails.
+    pub fn convert_field<O: CodecOps, F: Fn(&Dynamic<O>) -> DataResult<Dynamic<O>>>(
+        field_name: &str,
+        from: Type,
+        to: Type,
+        f: F,
+    ) -> impl TypeRewriteRule<O> + use<O, F> {
+        ConvertFieldRule {
+            field_name: field_name.to_string(),
+            from,
+            to,
+            f,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Applies a rule to the value of a field. The result is reversible if the inner rule is.
+    pub fn apply_to_field<O: CodecOps, R: TypeRewriteRule<O>>(
+        field_name: &str,
+        rule: R,
+    ) -> ApplyRuleToFieldRule<O, R> {
         ApplyRuleToFieldRule {
             field_name: field_name.to_string(),
             rule,
             _phantom: PhantomData,
         }
     }
+
+    /// Applies a rule to every value matching a path. The result is reversible if the inner rule is.
+    ///
+    /// A path is a list of field names separated by `.`, where each field name can be followed by
+    /// `[n]` to select the element at index `n` of a list, or `[*]` to select every element of a list.
+    /// For example, `settings.display[*].font_size` targets the `font_size` field of every element in the
+    /// `display` list of the `settings` object. Parts of the path that are not present in the value make the rule fail,
+    /// although [`TypeRewriteRule::fix_data`] skips them and still applies the rule to every other match.
+    ///
+    /// # Panics
+    /// Panics if the path is malformed. Use [`Rules::try_at`] for paths that are not known ahead of time.
+    pub fn at<O: CodecOps, R: TypeRewriteRule<O>>(path: &str, rule: R) -> AtPathRule<O, R> {
+        Rules::try_at(path, rule).unwrap_or_else(|e| panic!("invalid path {path:?}: {e}"))
+    }
+
+    /// Like [`Rules::at`], but returns an error if the path is malformed instead of panicking.
+    pub fn try_at<O: CodecOps, R: TypeRewriteRule<O>>(
+        path: &str,
+        rule: R,
+    ) -> DataResult<AtPathRule<O, R>> {
+        Ok(Rules::at_segments(PathSegment::parse(path)?, rule))
+    }
+
+    /// Like [`Rules::at`], but takes a path that is already split into segments. Field names are used as they are,
+    /// so they may contain `.`, `[` and `]`.
+    pub fn at_segments<O: CodecOps, R: TypeRewriteRule<O>>(
+        path: impl IntoIterator<Item = PathSegment>,
+        rule: R,
+    ) -> AtPathRule<O, R> {
+        AtPathRule {
+            path: path.into_iter().collect(),
+            rule,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Applies a rule to every element of a list. Fails if the value is not a list.
+    /// The result is reversible if the inner rule is.
+    pub fn each_element<O: CodecOps, R: TypeRewriteRule<O>>(rule: R) -> AtPathRule<O, R> {
+        AtPathRule {
+            path: [PathSegment::Each].into(),
+            rule,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// A rule that leaves values and types as they are.
+    pub fn identity<O: CodecOps>() -> IdentityRule<O> {
+        IdentityRule {
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Applies `rule` only to values matching `predicate`, leaving other values as they are.
+    /// See [`Predicates`] for commonly used predicates.
+    ///
+    /// [`Predicates`]: super::Predicates
+    pub fn when<O: CodecOps, P: Fn(&Dynamic<O>) -> bool, R: TypeRewriteRule<O>>(
+        predicate: P,
+        rule: R,
+    ) -> ConditionalRule<O, P, R, IdentityRule<O>> {
+        Rules::if_else(predicate, rule, Rules::identity())
+    }
+
+    /// Applies `then_rule` to values matching `predicate`, and `else_rule` to every other value.
+    ///
+    /// If the two rules produce different types, the resulting type is a [`Type::Either`] of both.
+    pub fn if_else<
+        O: CodecOps,
+        P: Fn(&Dynamic<O>) -> bool,
+        T: TypeRewriteRule<O>,
+        E: TypeRewriteRule<O>,
+    >(
+        predicate: P,
+        then_rule: T,
+        else_rule: E,
+    ) -> ConditionalRule<O, P, T, E> {
+        ConditionalRule {
+            predicate,
+            then_rule,
+            else_rule,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Reads the string tag stored in the field `tag` and applies the rule registered for that variant.
+    /// Values with a variant that has no rule are left as they are.
+    ///
+    /// On the type side, this rewrites the matching variants of a [`Type::TaggedChoice`] using the same tag.
+    /// Checking the type fails if a variant named here is not part of the tagged choice.
+    ///
+    /// Rules of different types can be added with [`DispatchRule::variant`].
+    pub fn dispatch<'a, O: CodecOps, R: TypeRewriteRule<O> + 'static>(
+        tag: &str,
+        variants: impl IntoIterator<Item = (&'a str, R)>,
+    ) -> DispatchRule<O> {
+        variants.into_iter().fold(
+            DispatchRule {
+                tag: tag.to_string(),
+                variants: BTreeMap::new(),
+            },
+            |rule, (name, variant)| rule.variant(name, variant),
+        )
+    }
+
+    /// Changes the tag stored in the field `tag` from `old_variant` to `new_variant`.
+    /// Values with any other variant are left as they are.
+    pub fn rename_variant<O: CodecOps>(
+        tag: &str,
+        old_variant: &str,
+        new_variant: &str,
+    ) -> impl ReversibleRule<O> + use<O> {
+        RenameVariantRule {
+            tag: tag.to_string(),
+            old_variant: old_variant.to_string(),
+            new_variant: new_variant.to_string(),
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Moves the fields `field_names` into a new object stored in the field `into`, for example moving
+    /// `x`, `y` and `z` into `pos`. Fields that are not present are skipped. Fails if `into` already exists.
+    ///
+    /// Reverting this rule moves the fields back out of `into`.
+    pub fn nest_fields<'a, O: CodecOps>(
+        field_names: impl IntoIterator<Item = &'a str>,
+        into: &str,
+    ) -> NestFieldsRule<O> {
+        NestFieldsRule {
+            field_names: field_names
+                .into_iter()
+                .map(|name| name.to_string())
+                .collect(),
+            into: into.to_string(),
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Moves every field of the object stored in the field `field_name` up into the containing object,
+    /// prepending `prefix` to their names. Values without the field are left as they are.
+    /// Fails if a moved field already exists.
+    ///
+    /// Reverting this rule moves every field whose name starts with `prefix` back into `field_name`, so `prefix`
+    /// should not be shared with the names of other fields. Values without such fields are left as they are.
+    pub fn flatten_field<O: CodecOps>(field_name: &str, prefix: &str) -> FlattenFieldRule<O> {
+        FlattenFieldRule {
+            field_name: field_name.to_string(),
+            prefix: prefix.to_string(),
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Converts a list of entries such as `[{ "key": "a", "value": 1 }]` into a map such as `{ "a": 1 }`,
+    /// reading each entry's key from `key_field` and its value from `value_field`.
+    /// Fails if an entry is missing either field, or if two entries have the same key.
+    ///
+    /// Reverting this rule converts the map back into a list of entries, see [`Rules::map_to_list`].
+    pub fn list_to_map<O: CodecOps>(key_field: &str, value_field: &str) -> ListToMapRule<O> {
+        ListToMapRule {
+            key_field: key_field.to_string(),
+            value_field: value_field.to_string(),
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Converts a map such as `{ "a": 1 }` into a list of entries such as `[{ "key": "a", "value": 1 }]`,
+    /// storing each key in `key_field` and each value in `value_field`. This is the reverse of [`Rules::list_to_map`].
+    pub fn map_to_list<O: CodecOps>(
+        key_field: &str,
+        value_field: &str,
+    ) -> ReversedRule<O, ListToMapRule<O>> {
+        Rules::reverse(Rules::list_to_map(key_field, value_field))
+    }
+
+    /// Renames every key of a map for which `f` returns a new name, such as namespacing `stone` to
+    /// `minecraft:stone`. Keys for which `f` returns `None` are kept. Fails if two entries end up with the same key.
+    ///
+    /// Unlike [`Rules::rename_field`], this works on maps whose keys are not known ahead of time,
+    /// such as those encoded from a `BTreeMap<String, T>`.
+    pub fn rename_map_keys<O: CodecOps, F: Fn(&str) -> Option<String>>(
+        f: F,
+    ) -> RenameMapKeysRule<O, F> {
+        RenameMapKeysRule {
+            f,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Applies `rule` to the value of every entry of a map. The result is reversible if the inner rule is.
+    pub fn map_values<O: CodecOps, R: TypeRewriteRule<O>>(rule: R) -> MapValuesRule<O, R> {
+        MapValuesRule {
+            rule,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Swaps the directions of a [`ReversibleRule`], so applying the result reverts the inner rule.
+    pub fn reverse<O: CodecOps, R: ReversibleRule<O>>(rule: R) -> ReversedRule<O, R> {
+        ReversedRule {
+            rule,
+            _phantom: PhantomData,
+        }
+    }
 }
 
 pub struct NewFieldRule<O: CodecOps, F: Fn(&Dynamic<O>) -> Dynamic<O>, G: Fn(&Type) -> Type> {
@@ -51,14 +316,13 @@ pub struct NewFieldRule<O: CodecOps, F: Fn(&Dynamic<O>) -> Dynamic<O>, G: Fn(&Ty
 impl<O: CodecOps, F: Fn(&Dynamic<O>) -> Dynamic<O>, G: Fn(&Type) -> Type> TypeRewriteRule<O>
     for NewFieldRule<O, F, G>
 {
-    fn fix_data(&self, ops: O, value: O::T) -> O::T {
+    fn try_fix_data(&self, ops: O, value: O::T, _ctx: &mut Context) -> DataResult<O::T> {
         let dynamic = Dynamic::new(value, ops.clone());
         let result = (self.value_function)(&dynamic);
         let mut value = dynamic.into_inner();
-        if let Ok(mut obj) = ops.get_map_mut(&mut value) {
-            obj.set(&self.field_name, result.into_inner());
-        }
-        value
+        ops.get_map_mut(&mut value)?
+            .set(&self.field_name, result.into_inner());
+        Ok(value)
     }
 
     fn fix_type(&self, mut ty: Type) -> Type {
@@ -70,6 +334,37 @@ impl<O: CodecOps, F: Fn(&Dynamic<O>) -> Dynamic<O>, G: Fn(&Type) -> Type> TypeRe
         }
         ty
     }
+
+    fn try_fix_type(&self, ty: Type) -> DataResult<Type> {
+        expect_object(&ty)?;
+        Ok(self.fix_type(ty))
+    }
+}
+
+impl<O: CodecOps, F: Fn(&Dynamic<O>) -> Dynamic<O>, G: Fn(&Type) -> Type> ReversibleRule<O>
+    for NewFieldRule<O, F, G>
+{
+    fn try_revert_data(&self, ops: O, value: O::T, ctx: &mut Context) -> DataResult<O::T> {
+        RemoveFieldRule {
+            field_name: self.field_name.clone(),
+            _phantom: PhantomData,
+        }
+        .try_fix_data(ops, value, ctx)
+    }
+
+    fn revert_type(&self, mut ty: Type) -> Type {
+        if let Type::Object(obj) = &mut ty {
+            let _ = obj.remove(&self.field_name);
+        }
+        ty
+    }
+
+    fn try_revert_type(&self, mut ty: Type) -> DataResult<Type> {
+        if let Some(object) = expect_object_mut(&mut ty)? {
+            object.remove(&self.field_name)?;
+        }
+        Ok(ty)
+    }
 }
 
 pub struct AndThenRule<O: CodecOps, L: TypeRewriteRule<O>, R: TypeRewriteRule<O>> {
@@ -81,6 +376,11 @@ pub struct AndThenRule<O: CodecOps, L: TypeRewriteRule<O>, R: TypeRewriteRule<O>
 impl<O: CodecOps, L: TypeRewriteRule<O>, R: TypeRewriteRule<O>> TypeRewriteRule<O>
     for AndThenRule<O, L, R>
 {
+    fn try_fix_data(&self, ops: O, value: O::T, ctx: &mut Context) -> DataResult<O::T> {
+        let value = self.left.try_fix_data(ops.clone(), value, ctx)?;
+        self.right.try_fix_data(ops, value, ctx)
+    }
+
     fn fix_data(&self, ops: O, value: O::T) -> O::T {
         self.right
             .fix_data(ops.clone(), self.left.fix_data(ops, value))
@@ -89,6 +389,32 @@ impl<O: CodecOps, L: TypeRewriteRule<O>, R: TypeRewriteRule<O>> TypeRewriteRule<
     fn fix_type(&self, ty: Type) -> Type {
         self.right.fix_type(self.left.fix_type(ty))
     }
+
+    fn try_fix_type(&self, ty: Type) -> DataResult<Type> {
+        self.right.try_fix_type(self.left.try_fix_type(ty)?)
+    }
+}
+
+impl<O: CodecOps, L: ReversibleRule<O>, R: ReversibleRule<O>> ReversibleRule<O>
+    for AndThenRule<O, L, R>
+{
+    fn try_revert_data(&self, ops: O, value: O::T, ctx: &mut Context) -> DataResult<O::T> {
+        let value = self.right.try_revert_data(ops.clone(), value, ctx)?;
+        self.left.try_revert_data(ops, value, ctx)
+    }
+
+    fn revert_data(&self, ops: O, value: O::T) -> O::T {
+        self.left
+            .revert_data(ops.clone(), self.right.revert_data(ops, value))
+    }
+
+    fn revert_type(&self, ty: Type) -> Type {
+        self.left.revert_type(self.right.revert_type(ty))
+    }
+
+    fn try_revert_type(&self, ty: Type) -> DataResult<Type> {
+        self.left.try_revert_type(self.right.try_revert_type(ty)?)
+    }
 }
 
 pub struct ApplyRuleToFieldRule<O: CodecOps, R: TypeRewriteRule<O>> {
@@ -97,28 +423,87 @@ pub struct ApplyRuleToFieldRule<O: CodecOps, R: TypeRewriteRule<O>> {
     _phantom: PhantomData<O>,
 }
 
-impl<O: CodecOps, R: TypeRewriteRule<O>> TypeRewriteRule<O> for ApplyRuleToFieldRule<O, R> {
-    fn fix_data(&self, ops: O, mut value: O::T) -> O::T {
-        if ops.get_map(&value).is_ok() {
-            let mut object = ops.get_map_mut(&mut value).unwrap();
-            if let Ok(field_value) = object.get(&self.field_name) {
-                object.set(
-                    &self.field_name,
-                    self.rule.fix_data(ops.clone(), field_value.clone()),
-                );
-            }
+impl<O: CodecOps, R: TypeRewriteRule<O>> ApplyRuleToFieldRule<O, R> {
+    fn update_field(
+        &self,
+        ops: O,
+        mut value: O::T,
+        ctx: &mut Context,
+        f: impl Fn(O, O::T, &mut Context) -> DataResult<O::T>,
+    ) -> DataResult<O::T> {
+        {
+            let mut object = ops.get_map_mut(&mut value)?;
+            ctx.push_field(&self.field_name);
+            let field_value = f(ops.clone(), object.get(&self.field_name)?.clone(), ctx)?;
+            object.set(&self.field_name, field_value);
+            ctx.pop();
         }
-        value
+        Ok(value)
+    }
+}
+
+impl<O: CodecOps, R: TypeRewriteRule<O>> TypeRewriteRule<O> for ApplyRuleToFieldRule<O, R> {
+    fn try_fix_data(&self, ops: O, value: O::T, ctx: &mut Context) -> DataResult<O::T> {
+        self.update_field(ops, value, ctx, |ops, value, ctx| {
+            self.rule.try_fix_data(ops, value, ctx)
+        })
+    }
+
+    fn fix_data(&self, ops: O, value: O::T) -> O::T {
+        self.update_field(ops, value.clone(), &mut Context::new(), |ops, value, _| {
+            Ok(self.rule.fix_data(ops, value))
+        })
+        .unwrap_or(value)
     }
 
     fn fix_type(&self, mut ty: Type) -> Type {
-        if let Type::Object(object) = &mut ty {
-            if let Ok(field) = object.remove(&self.field_name) {
-                object.insert(&self.field_name, self.rule.fix_type(field));
-            }
+        if let Type::Object(object) = &mut ty
+            && let Ok(field) = object.remove(&self.field_name)
+        {
+            object.insert(&self.field_name, self.rule.fix_type(field));
+        }
+        ty
+    }
+
+    fn try_fix_type(&self, mut ty: Type) -> DataResult<Type> {
+        if let Some(object) = expect_object_mut(&mut ty)? {
+            let field = object.remove(&self.field_name)?;
+            object.insert(&self.field_name, self.rule.try_fix_type(field)?);
+        }
+        Ok(ty)
+    }
+}
+
+impl<O: CodecOps, R: ReversibleRule<O>> ReversibleRule<O> for ApplyRuleToFieldRule<O, R> {
+    fn try_revert_data(&self, ops: O, value: O::T, ctx: &mut Context) -> DataResult<O::T> {
+        self.update_field(ops, value, ctx, |ops, value, ctx| {
+            self.rule.try_revert_data(ops, value, ctx)
+        })
+    }
+
+    fn revert_data(&self, ops: O, value: O::T) -> O::T {
+        self.update_field(ops, value.clone(), &mut Context::new(), |ops, value, _| {
+            Ok(self.rule.revert_data(ops, value))
+        })
+        .unwrap_or(value)
+    }
+
+    fn revert_type(&self, mut ty: Type) -> Type {
+        if let Type::Object(object) = &mut ty
+            && let Ok(field) = object.remove(&self.field_name)
+        {
+            object.insert(&self.field_name, self.rule.revert_type(field));
         }
         ty
     }
+
+    fn try_revert_type(&self, mut ty: Type) -> DataResult<Type> {
+        if let Some(object) = expect_object_mut(&mut ty)? {
+            let field = object.remove(&self.field_name)?;
+            object.insert(&self.field_name, self.rule.try_revert_type(field)?);
+        }
+        Ok(ty)
+    }
 }
 
 pub struct RemoveFieldRule<O: CodecOps> {
@@ -127,21 +512,1214 @@ pub struct RemoveFieldRule<O: CodecOps> {
 }
 
 impl<O: CodecOps> TypeRewriteRule<O> for RemoveFieldRule<O> {
-    fn fix_data(&self, ops: O, mut value: O::T) -> O::T {
+    fn try_fix_data(&self, ops: O, mut value: O::T, _ctx: &mut Context) -> DataResult<O::T> {
+        ops.get_map_mut(&mut value)?.remove(&self.field_name)?;
+        Ok(value)
+    }
+
+    fn fix_type(&self, mut ty: Type) -> Type {
         {
-            if let Ok(mut obj) = ops.get_map_mut(&mut value) {
+            if let Type::Object(obj) = &mut ty {
                 let _ = obj.remove(&self.field_name);
             }
         }
-        value
+        ty
+    }
+
+    fn try_fix_type(&self, mut ty: Type) -> DataResult<Type> {
+        if let Some(object) = expect_object_mut(&mut ty)? {
+            object.remove(&self.field_name)?;
+        }
+        Ok(ty)
+    }
+}
+
+pub struct RenameFieldRule<O: CodecOps> {
+    old_name: String,
+    new_name: String,
+    _phantom: PhantomData<O>,
+}
+
+impl<O: CodecOps> RenameFieldRule<O> {
+    fn move_data(
+        ops: O,
+        mut value: O::T,
+        from: &str,
+        to: &str,
+        ctx: &mut Context,
+    ) -> DataResult<O::T> {
+        {
+            let mut obj = ops.get_map_mut(&mut value)?;
+            if from != to && obj.get(to).is_ok() {
+                ctx.push_field(to);
+                return Err(DataError::new_custom(&format!(
+                    "field {} already exists",
+                    to
+                )));
+            }
+            let field_value = obj.remove(from)?;
+            obj.set(to, field_value);
+        }
+        Ok(value)
+    }
+
+    fn move_type(mut ty: Type, from: &str, to: &str) -> Type {
+        if let Type::Object(obj) = &mut ty
+            && obj.get(to).is_err()
+            && let Ok(field) = obj.remove(from)
+        {
+            obj.insert(to, field);
+        }
+        ty
+    }
+
+    fn try_move_type(ty: Type, from: &str, to: &str) -> DataResult<Type> {
+        if let Some(object) = expect_object(&ty)? {
+            object.get(from)?;
+            if from != to && object.get(to).is_ok() {
+                return Err(DataError::new_custom(&format!(
+                    "field {} already exists",
+                    to
+                )));
+            }
+        }
+        Ok(Self::move_type(ty, from, to))
+    }
+}
+
+impl<O: CodecOps> TypeRewriteRule<O> for RenameFieldRule<O> {
+    fn try_fix_data(&self, ops: O, value: O::T, ctx: &mut Context) -> DataResult<O::T> {
+        Self::move_data(ops, value, &self.old_name, &self.new_name, ctx)
+    }
+
+    fn fix_type(&self, ty: Type) -> Type {
+        Self::move_type(ty, &self.old_name, &self.new_name)
+    }
+
+    fn try_fix_type(&self, ty: Type) -> DataResult<Type> {
+        Self::try_move_type(ty, &self.old_name, &self.new_name)
+    }
+}
+
+impl<O: CodecOps> ReversibleRule<O> for RenameFieldRule<O> {
+    fn try_revert_data(&self, ops: O, value: O::T, ctx: &mut Context) -> DataResult<O::T> {
+        Self::move_data(ops, value, &self.new_name, &self.old_name, ctx)
+    }
+
+    fn revert_type(&self, ty: Type) -> Type {
+        Self::move_type(ty, &self.new_name, &self.old_name)
+    }
+
+    fn try_revert_type(&self, ty: Type) -> DataResult<Type> {
+        Self::try_move_type(ty, &self.new_name, &self.old_name)
+    }
+}
+
+pub struct ConvertFieldRule<O: CodecOps, F: Fn(&Dynamic<O>) -> DataResult<Dynamic<O>>> {
+    field_name: String,
+    from: Type,
+    to: Type,
+    f: F,
+    _phantom: PhantomData<O>,
+}
+
+impl<O: CodecOps, F: Fn(&Dynamic<O>) -> DataResult<Dynamic<O>>> TypeRewriteRule<O>
+    for ConvertFieldRule<O, F>
+{
+    fn try_fix_data(&self, ops: O, mut value: O::T, ctx: &mut Context) -> DataResult<O::T> {
+        {
+            let mut obj = ops.get_map_mut(&mut value)?;
+            ctx.push_field(&self.field_name);
+            let field = Dynamic::new(obj.get(&self.field_name)?.clone(), ops.clone());
+            let converted = (self.f)(&field)?;
+            obj.set(&self.field_name, converted.into_inner());
+            ctx.pop();
+        }
+        Ok(value)
     }
 
     fn fix_type(&self, mut ty: Type) -> Type {
+        if let Type::Object(obj) = &mut ty
+            && obj.get(&self.field_name).is_ok_and(|ty| ty == self.from)
         {
-            if let Type::Object(obj) = &mut ty {
-                let _ = obj.remove(&self.field_name);
+            obj.insert(&self.field_name, self.to.clone());
+        }
+        ty
+    }
+
+    fn try_fix_type(&self, mut ty: Type) -> DataResult<Type> {
+        if let Some(object) = expect_object_mut(&mut ty)? {
+            let field = object.get(&self.field_name)?;
+            if field != self.from && field != Type::Unknown {
+                return Err(DataError::new_custom(&format!(
+                    "expected field {} to have type {:?}, found {:?}",
+                    self.field_name, self.from, field
+                )));
             }
+            object.insert(&self.field_name, self.to.clone());
         }
+        Ok(ty)
+    }
+}
+
+pub struct IdentityRule<O: CodecOps> {
+    _phantom: PhantomData<O>,
+}
+
+impl<O: CodecOps> TypeRewriteRule<O> for IdentityRule<O> {
+    fn try_fix_data(&self, _ops: O, value: O::T, _ctx: &mut Context) -> DataResult<O::T> {
+        Ok(value)
+    }
+
+    fn fix_type(&self, ty: Type) -> Type {
         ty
     }
 }
+
+impl<O: CodecOps> ReversibleRule<O> for IdentityRule<O> {
+    fn try_revert_data(&self, _ops: O, value: O::T, _ctx: &mut Context) -> DataResult<O::T> {
+        Ok(value)
+    }
+
+    fn revert_type(&self, ty: Type) -> Type {
+        ty
+    }
+}
+
+pub struct ConditionalRule<
+    O: CodecOps,
+    P: Fn(&Dynamic<O>) -> bool,
+    T: TypeRewriteRule<O>,
+    E: TypeRewriteRule<O>,
+> {
+    predicate: P,
+    then_rule: T,
+    else_rule: E,
+    _phantom: PhantomData<O>,
+}
+
+impl<O: CodecOps, P: Fn(&Dynamic<O>) -> bool, T: TypeRewriteRule<O>, E: TypeRewriteRule<O>>
+    TypeRewriteRule<O> for ConditionalRule<O, P, T, E>
+{
+    fn try_fix_data(&self, ops: O, value: O::T, ctx: &mut Context) -> DataResult<O::T> {
+        let dynamic = Dynamic::new(value, ops.clone());
+        if (self.predicate)(&dynamic) {
+            self.then_rule.try_fix_data(ops, dynamic.into_inner(), ctx)
+        } else {
+            self.else_rule.try_fix_data(ops, dynamic.into_inner(), ctx)
+        }
+    }
+
+    fn fix_data(&self, ops: O, value: O::T) -> O::T {
+        let dynamic = Dynamic::new(value, ops.clone());
+        if (self.predicate)(&dynamic) {
+            self.then_rule.fix_data(ops, dynamic.into_inner())
+        } else {
+            self.else_rule.fix_data(ops, dynamic.into_inner())
+        }
+    }
+
+    fn fix_type(&self, ty: Type) -> Type {
+        let then_type = self.then_rule.fix_type(ty.clone());
+        let else_type = self.else_rule.fix_type(ty);
+        Self::join(then_type, else_type)
+    }
+
+    fn try_fix_type(&self, ty: Type) -> DataResult<Type> {
+        let then_type = self.then_rule.try_fix_type(ty.clone())?;
+        let else_type = self.else_rule.try_fix_type(ty)?;
+        Ok(Self::join(then_type, else_type))
+    }
+}
+
+impl<O: CodecOps, P: Fn(&Dynamic<O>) -> bool, T: TypeRewriteRule<O>, E: TypeRewriteRule<O>>
+    ConditionalRule<O, P, T, E>
+{
+    fn join(then_type: Type, else_type: Type) -> Type {
+        if then_type == else_type {
+            then_type
+        } else {
+            Type::Either(EitherType::new(then_type, else_type))
+        }
+    }
+}
+
+fn read_tag<O: CodecOps>(
+    ops: &O,
+    value: &O::T,
+    tag: &str,
+    ctx: &mut Context,
+) -> DataResult<String> {
+    ctx.push_field(tag);
+    let variant = ops.get_string(ops.get_map(value)?.get(tag)?)?;
+    ctx.pop();
+    Ok(variant)
+}
+
+pub struct DispatchRule<O: CodecOps> {
+    tag: String,
+    variants: BTreeMap<String, Box<dyn TypeRewriteRule<O>>>,
+}
+
+impl<O: CodecOps> DispatchRule<O> {
+    /// Applies `rule` to values whose tag is `name`, replacing any rule already registered for it.
+    pub fn variant(mut self, name: &str, rule: impl TypeRewriteRule<O> + 'static) -> Self {
+        self.variants.insert(name.to_string(), Box::new(rule));
+        self
+    }
+}
+
+impl<O: CodecOps> TypeRewriteRule<O> for DispatchRule<O> {
+    fn try_fix_data(&self, ops: O, value: O::T, ctx: &mut Context) -> DataResult<O::T> {
+        let variant = read_tag(&ops, &value, &self.tag, ctx)?;
+        match self.variants.get(&variant) {
+            Some(rule) => rule.try_fix_data(ops, value, ctx),
+            None => Ok(value),
+        }
+    }
+
+    fn fix_data(&self, ops: O, value: O::T) -> O::T {
+        let variant = read_tag(&ops, &value, &self.tag, &mut Context::new());
+        match variant.ok().and_then(|variant| self.variants.get(&variant)) {
+            Some(rule) => rule.fix_data(ops, value),
+            None => value,
+        }
+    }
+
+    fn fix_type(&self, mut ty: Type) -> Type {
+        if let Type::TaggedChoice(choice) = &mut ty
+            && choice.tag() == self.tag
+        {
+            for (name, rule) in &self.variants {
+                if let Ok(variant) = choice.remove_variant(name) {
+                    choice.insert_variant(name, rule.fix_type(variant));
+                }
+            }
+        }
+        ty
+    }
+
+    fn try_fix_type(&self, mut ty: Type) -> DataResult<Type> {
+        match &mut ty {
+            Type::TaggedChoice(choice) if choice.tag() == self.tag => {
+                for (name, rule) in &self.variants {
+                    let variant = choice.remove_variant(name)?;
+                    choice.insert_variant(name, rule.try_fix_type(variant)?);
+                }
+                Ok(ty)
+            }
+            Type::Unknown => Ok(ty),
+            _ => Err(DataError::unexpected_type(&format!(
+                "tagged choice on {}",
+                self.tag
+            ))),
+        }
+    }
+}
+
+pub struct RenameVariantRule<O: CodecOps> {
+    tag: String,
+    old_variant: String,
+    new_variant: String,
+    _phantom: PhantomData<O>,
+}
+
+impl<O: CodecOps> RenameVariantRule<O> {
+    fn move_data(
+        &self,
+        ops: O,
+        mut value: O::T,
+        ctx: &mut Context,
+        from: &str,
+        to: &str,
+    ) -> DataResult<O::T> {
+        if read_tag(&ops, &value, &self.tag, ctx)? == from {
+            let tag = ops.create_string(to);
+            ops.get_map_mut(&mut value)?.set(&self.tag, tag);
+        }
+        Ok(value)
+    }
+
+    fn move_type(&self, mut ty: Type, from: &str, to: &str) -> Type {
+        if let Type::TaggedChoice(choice) = &mut ty
+            && choice.tag() == self.tag
+            && let Ok(variant) = choice.remove_variant(from)
+        {
+            choice.insert_variant(to, variant);
+        }
+        ty
+    }
+
+    fn try_move_type(&self, ty: Type, from: &str, to: &str) -> DataResult<Type> {
+        match &ty {
+            Type::TaggedChoice(choice) if choice.tag() == self.tag => {
+                choice.get_variant(from)?;
+                if from != to && choice.get_variant(to).is_ok() {
+                    return Err(DataError::new_custom(&format!(
+                        "variant {} already exists",
+                        to
+                    )));
+                }
+                Ok(self.move_type(ty, from, to))
+            }
+            Type::Unknown => Ok(ty),
+            _ => Err(DataError::unexpected_type(&format!(
+                "tagged choice on {}",
+                self.tag
+            ))),
+        }
+    }
+}
+
+impl<O: CodecOps> TypeRewriteRule<O> for RenameVariantRule<O> {
+    fn try_fix_data(&self, ops: O, value: O::T, ctx: &mut Context) -> DataResult<O::T> {
+        self.move_data(ops, value, ctx, &self.old_variant, &self.new_variant)
+    }
+
+    fn fix_type(&self, ty: Type) -> Type {
+        self.move_type(ty, &self.old_variant, &self.new_variant)
+    }
+
+    fn try_fix_type(&self, ty: Type) -> DataResult<Type> {
+        self.try_move_type(ty, &self.old_variant, &self.new_variant)
+    }
+}
+
+impl<O: CodecOps> ReversibleRule<O> for RenameVariantRule<O> {
+    fn try_revert_data(&self, ops: O, value: O::T, ctx: &mut Context) -> DataResult<O::T> {
+        self.move_data(ops, value, ctx, &self.new_variant, &self.old_variant)
+    }
+
+    fn revert_type(&self, ty: Type) -> Type {
+        self.move_type(ty, &self.new_variant, &self.old_variant)
+    }
+
+    fn try_revert_type(&self, ty: Type) -> DataResult<Type> {
+        self.try_move_type(ty, &self.new_variant, &self.old_variant)
+    }
+}
+
+pub struct NestFieldsRule<O: CodecOps> {
+    field_names: Vec<String>,
+    into: String,
+    _phantom: PhantomData<O>,
+}
+
+impl<O: CodecOps> TypeRewriteRule<O> for NestFieldsRule<O> {
+    fn try_fix_data(&self, ops: O, mut value: O::T, ctx: &mut Context) -> DataResult<O::T> {
+        {
+            let mut object = ops.get_map_mut(&mut value)?;
+            if object.get(&self.into).is_ok() {
+                ctx.push_field(&self.into);
+                return Err(DataError::new_custom(&format!(
+                    "field {} already exists",
+                    self.into
+                )));
+            }
+            let nested = self
+                .field_names
+                .iter()
+                .filter_map(|name| Some((name.clone(), object.remove(name).ok()?)))
+                .collect::<Vec<_>>();
+            object.set(&self.into, ops.create_map(nested));
+        }
+        Ok(value)
+    }
+
+    fn fix_type(&self, mut ty: Type) -> Type {
+        if let Type::Object(object) = &mut ty {
+            let mut nested = ObjectType::new();
+            for name in &self.field_names {
+                if let Ok(field) = object.remove(name) {
+                    nested.insert(name, field);
+                }
+            }
+            object.insert(&self.into, Type::Object(nested));
+        }
+        ty
+    }
+
+    fn try_fix_type(&self, ty: Type) -> DataResult<Type> {
+        if let Some(object) = expect_object(&ty)?
+            && object.get(&self.into).is_ok()
+        {
+            return Err(DataError::new_custom(&format!(
+                "field {} already exists",
+                self.into
+            )));
+        }
+        Ok(self.fix_type(ty))
+    }
+}
+
+impl<O: CodecOps> ReversibleRule<O> for NestFieldsRule<O> {
+    fn try_revert_data(&self, ops: O, mut value: O::T, ctx: &mut Context) -> DataResult<O::T> {
+        {
+            let mut object = ops.get_map_mut(&mut value)?;
+            ctx.push_field(&self.into);
+            let mut nested = object.remove(&self.into)?;
+            let mut nested = ops.get_map_mut(&mut nested)?;
+            ctx.pop();
+            for name in &self.field_names {
+                if let Ok(field) = nested.remove(name) {
+                    object.set(name, field);
+                }
+            }
+        }
+        Ok(value)
+    }
+
+    fn revert_type(&self, mut ty: Type) -> Type {
+        if let Type::Object(object) = &mut ty
+            && let Ok(Type::Object(mut nested)) = object.remove(&self.into)
+        {
+            for name in &self.field_names {
+                if let Ok(field) = nested.remove(name) {
+                    object.insert(name, field);
+                }
+            }
+        }
+        ty
+    }
+}
+
+pub struct FlattenFieldRule<O: CodecOps> {
+    field_name: String,
+    prefix: String,
+    _phantom: PhantomData<O>,
+}
+
+impl<O: CodecOps> TypeRewriteRule<O> for FlattenFieldRule<O> {
+    fn try_fix_data(&self, ops: O, mut value: O::T, ctx: &mut Context) -> DataResult<O::T> {
+        // The field may be optional, in which case there is nothing to flatten.
+        if ops.get_map(&value)?.get(&self.field_name).is_err() {
+            return Ok(value);
+        }
+        {
+            let mut object = ops.get_map_mut(&mut value)?;
+            ctx.push_field(&self.field_name);
+            let nested = object.remove(&self.field_name)?;
+            let nested = ops.get_map(&nested)?;
+            ctx.pop();
+            for key in nested.keys() {
+                let name = format!("{}{}", self.prefix, key);
+                if object.get(&name).is_ok() {
+                    ctx.push_field(&name);
+                    return Err(DataError::new_custom(&format!(
+                        "field {} already exists",
+                        name
+                    )));
+                }
+                object.set(&name, nested.get(&key)?.clone());
+            }
+        }
+        Ok(value)
+    }
+
+    fn fix_type(&self, mut ty: Type) -> Type {
+        if let Type::Object(object) = &mut ty {
+            let nested = match object.get(&self.field_name) {
+                Ok(Type::Object(nested)) => (nested, false),
+                Ok(Type::Optional(optional)) => match optional.ty() {
+                    Type::Object(nested) => (nested.clone(), true),
+                    _ => return ty,
+                },
+                _ => return ty,
+            };
+            let _ = object.remove(&self.field_name);
+            let (nested, optional) = nested;
+            for (name, field) in nested.fields() {
+                let field = match field {
+                    Type::Optional(_) => field.clone(),
+                    _ if optional => Type::Optional(OptionalType::new(field.clone())),
+                    _ => field.clone(),
+                };
+                object.insert(&format!("{}{}", self.prefix, name), field);
+            }
+        }
+        ty
+    }
+
+    fn try_fix_type(&self, ty: Type) -> DataResult<Type> {
+        if let Some(object) = expect_object(&ty)?
+            && let Ok(field) = object.get(&self.field_name)
+        {
+            let field = match &field {
+                Type::Optional(optional) => optional.ty(),
+                field => field,
+            };
+            if let Some(nested) = expect_object(field)? {
+                for (name, _) in nested.fields() {
+                    let name = format!("{}{}", self.prefix, name);
+                    if object.get(&name).is_ok() {
+                        return Err(DataError::new_custom(&format!(
+                            "field {} already exists",
+                            name
+                        )));
+                    }
+                }
+            }
+        }
+        Ok(self.fix_type(ty))
+    }
+}
+
+impl<O: CodecOps> ReversibleRule<O> for FlattenFieldRule<O> {
+    fn try_revert_data(&self, ops: O, mut value: O::T, ctx: &mut Context) -> DataResult<O::T> {
+        {
+            let mut object = ops.get_map_mut(&mut value)?;
+            if object.get(&self.field_name).is_ok() {
+                ctx.push_field(&self.field_name);
+                return Err(DataError::new_custom(&format!(
+                    "field {} already exists",
+                    self.field_name
+                )));
+            }
+            let nested = object
+                .keys()
+                .into_iter()
+                .filter_map(|name| {
+                    let key = name.strip_prefix(&self.prefix)?.to_string();
+                    Some((key, object.remove(&name).ok()?))
+                })
+                .collect::<Vec<_>>();
+            if !nested.is_empty() {
+                object.set(&self.field_name, ops.create_map(nested));
+            }
+        }
+        Ok(value)
+    }
+
+    fn revert_type(&self, mut ty: Type) -> Type {
+        if let Type::Object(object) = &mut ty {
+            let mut nested = ObjectType::new();
+            let names = object
+                .fields()
+                .map(|(name, _)| name.to_string())
+                .collect::<Vec<_>>();
+            for name in names {
+                if let Some(key) = name.strip_prefix(&self.prefix)
+                    && let Ok(field) = object.remove(&name)
+                {
+                    nested.insert(key, field);
+                }
+            }
+            if nested.fields().next().is_some() {
+                object.insert(&self.field_name, Type::Object(nested));
+            }
+        }
+        ty
+    }
+
+    fn try_revert_type(&self, ty: Type) -> DataResult<Type> {
+        if let Some(object) = expect_object(&ty)?
+            && object.get(&self.field_name).is_ok()
+        {
+            return Err(DataError::new_custom(&format!(
+                "field {} already exists",
+                self.field_name
+            )));
+        }
+        Ok(self.revert_type(ty))
+    }
+}
+
+pub struct ListToMapRule<O: CodecOps> {
+    key_field: String,
+    value_field: String,
+    _phantom: PhantomData<O>,
+}
+
+impl<O: CodecOps> ListToMapRule<O> {
+    fn entry_type(&self, value: Type) -> Type {
+        Type::Object(
+            ObjectType::new()
+                .field(&self.key_field, Type::String)
+                .field(&self.value_field, value),
+        )
+    }
+}
+
+impl<O: CodecOps> TypeRewriteRule<O> for ListToMapRule<O> {
+    fn try_fix_data(&self, ops: O, value: O::T, ctx: &mut Context) -> DataResult<O::T> {
+        let mut entries: Vec<(String, O::T)> = Vec::new();
+        for (index, entry) in ops.get_list(&value)?.into_iter().enumerate() {
+            ctx.push_array(index);
+            let entry = ops.get_map(&entry)?;
+            ctx.push_field(&self.key_field);
+            let key = ops.get_string(entry.get(&self.key_field)?)?;
+            if entries.iter().any(|(existing, _)| *existing == key) {
+                return Err(DataError::new_custom(&format!("duplicate key \"{}\"", key)));
+            }
+            ctx.pop();
+            ctx.push_field(&self.value_field);
+            entries.push((key, entry.get(&self.value_field)?.clone()));
+            ctx.pop();
+            ctx.pop();
+        }
+        Ok(ops.create_map(entries))
+    }
+
+    fn fix_type(&self, ty: Type) -> Type {
+        if let Type::Array(array) = &ty
+            && let Type::Object(entry) = array.ty()
+            && let Ok(value) = entry.get(&self.value_field)
+        {
+            return Type::Map(MapType::new(value));
+        }
+        ty
+    }
+
+    fn try_fix_type(&self, ty: Type) -> DataResult<Type> {
+        match &ty {
+            Type::Unknown => Ok(ty),
+            Type::Array(array) => match array.ty() {
+                Type::Unknown => Ok(Type::Map(MapType::new(Type::Unknown))),
+                Type::Object(entry) => {
+                    if !entry.get(&self.key_field)?.is_compatible(&Type::String) {
+                        return Err(DataError::unexpected_type("string"));
+                    }
+                    Ok(Type::Map(MapType::new(entry.get(&self.value_field)?)))
+                }
+                _ => Err(DataError::unexpected_type("object")),
+            },
+            _ => Err(DataError::unexpected_type("list")),
+        }
+    }
+}
+
+impl<O: CodecOps> ReversibleRule<O> for ListToMapRule<O> {
+    fn try_revert_data(&self, ops: O, value: O::T, _ctx: &mut Context) -> DataResult<O::T> {
+        let map = ops.get_map(&value)?;
+        let mut entries = Vec::new();
+        for key in map.keys() {
+            let entry_value = map.get(&key)?.clone();
+            entries.push(ops.create_map([
+                (self.key_field.clone(), ops.create_string(&key)),
+                (self.value_field.clone(), entry_value),
+            ]));
+        }
+        Ok(ops.create_list(entries))
+    }
+
+    fn revert_type(&self, ty: Type) -> Type {
+        match ty {
+            Type::Map(map) => Type::Array(ArrayType::new(self.entry_type(map.ty().clone()))),
+            ty => ty,
+        }
+    }
+
+    fn try_revert_type(&self, ty: Type) -> DataResult<Type> {
+        match ty {
+            Type::Map(_) | Type::Unknown => Ok(self.revert_type(ty)),
+            _ => Err(DataError::unexpected_type("map")),
+        }
+    }
+}
+
+pub struct RenameMapKeysRule<O: CodecOps, F: Fn(&str) -> Option<String>> {
+    f: F,
+    _phantom: PhantomData<O>,
+}
+
+impl<O: CodecOps, F: Fn(&str) -> Option<String>> TypeRewriteRule<O> for RenameMapKeysRule<O, F> {
+    fn try_fix_data(&self, ops: O, value: O::T, ctx: &mut Context) -> DataResult<O::T> {
+        let map = ops.get_map(&value)?;
+        let mut entries: Vec<(String, O::T)> = Vec::new();
+        for key in map.keys() {
+            let entry = map.get(&key)?.clone();
+            let key = (self.f)(&key).unwrap_or(key);
+            if entries.iter().any(|(existing, _)| *existing == key) {
+                ctx.push_field(&key);
+                return Err(DataError::new_custom(&format!("duplicate key \"{}\"", key)));
+            }
+            entries.push((key, entry));
+        }
+        Ok(ops.create_map(entries))
+    }
+
+    fn fix_type(&self, mut ty: Type) -> Type {
+        if let Type::Object(object) = &mut ty {
+            let fields = object
+                .fields()
+                .map(|(name, field)| ((self.f)(name).unwrap_or(name.to_string()), field.clone()))
+                .collect::<Vec<_>>();
+            *object = ObjectType::new();
+            for (name, field) in fields {
+                object.insert(&name, field);
+            }
+        }
+        ty
+    }
+
+    fn try_fix_type(&self, ty: Type) -> DataResult<Type> {
+        match &ty {
+            Type::Map(_) | Type::Object(_) | Type::Unknown => Ok(self.fix_type(ty)),
+            _ => Err(DataError::unexpected_type("map")),
+        }
+    }
+}
+
+pub struct MapValuesRule<O: CodecOps, R: TypeRewriteRule<O>> {
+    rule: R,
+    _phantom: PhantomData<O>,
+}
+
+impl<O: CodecOps, R: TypeRewriteRule<O>> MapValuesRule<O, R> {
+    fn update_values(
+        ops: O,
+        mut value: O::T,
+        ctx: &mut Context,
+        f: impl Fn(O, O::T, &mut Context) -> DataResult<O::T>,
+    ) -> DataResult<O::T> {
+        {
+            let mut map = ops.get_map_mut(&mut value)?;
+            for key in map.keys() {
+                ctx.push_field(&key);
+                let entry = map.get_mut(&key)?;
+                *entry = f(ops.clone(), entry.clone(), ctx)?;
+                ctx.pop();
+            }
+        }
+        Ok(value)
+    }
+
+    fn update_types(
+        mut ty: Type,
+        f: impl Fn(Type) -> DataResult<Type>,
+        strict: bool,
+    ) -> DataResult<Type> {
+        match &mut ty {
+            Type::Map(map) => Ok(Type::Map(MapType::new(f(map.ty().clone())?))),
+            Type::Object(object) => {
+                let fields = object
+                    .fields()
+                    .map(|(name, field)| Ok((name.to_string(), f(field.clone())?)))
+                    .collect::<DataResult<Vec<_>>>()?;
+                for (name, field) in fields {
+                    object.insert(&name, field);
+                }
+                Ok(ty)
+            }
+            Type::Unknown => Ok(ty),
+            _ if strict => Err(DataError::unexpected_type("map")),
+            _ => Ok(ty),
+        }
+    }
+}
+
+impl<O: CodecOps, R: TypeRewriteRule<O>> TypeRewriteRule<O> for MapValuesRule<O, R> {
+    fn try_fix_data(&self, ops: O, value: O::T, ctx: &mut Context) -> DataResult<O::T> {
+        Self::update_values(ops, value, ctx, |ops, value, ctx| {
+            self.rule.try_fix_data(ops, value, ctx)
+        })
+    }
+
+    fn fix_data(&self, ops: O, value: O::T) -> O::T {
+        Self::update_values(ops, value.clone(), &mut Context::new(), |ops, value, _| {
+            Ok(self.rule.fix_data(ops, value))
+        })
+        .unwrap_or(value)
+    }
+
+    fn fix_type(&self, ty: Type) -> Type {
+        let fallback = ty.clone();
+        Self::update_types(ty, |ty| Ok(self.rule.fix_type(ty)), false).unwrap_or(fallback)
+    }
+
+    fn try_fix_type(&self, ty: Type) -> DataResult<Type> {
+        Self::update_types(ty, |ty| self.rule.try_fix_type(ty), true)
+    }
+}
+
+impl<O: CodecOps, R: ReversibleRule<O>> ReversibleRule<O> for MapValuesRule<O, R> {
+    fn try_revert_data(&self, ops: O, value: O::T, ctx: &mut Context) -> DataResult<O::T> {
+        Self::update_values(ops, value, ctx, |ops, value, ctx| {
+            self.rule.try_revert_data(ops, value, ctx)
+        })
+    }
+
+    fn revert_data(&self, ops: O, value: O::T) -> O::T {
+        Self::update_values(ops, value.clone(), &mut Context::new(), |ops, value, _| {
+            Ok(self.rule.revert_data(ops, value))
+        })
+        .unwrap_or(value)
+    }
+
+    fn revert_type(&self, ty: Type) -> Type {
+        let fallback = ty.clone();
+        Self::update_types(ty, |ty| Ok(self.rule.revert_type(ty)), false).unwrap_or(fallback)
+    }
+
+    fn try_revert_type(&self, ty: Type) -> DataResult<Type> {
+        Self::update_types(ty, |ty| self.rule.try_revert_type(ty), true)
+    }
+}
+
+pub struct ReversedRule<O: CodecOps, R: ReversibleRule<O>> {
+    rule: R,
+    _phantom: PhantomData<O>,
+}
+
+impl<O: CodecOps, R: ReversibleRule<O>> TypeRewriteRule<O> for ReversedRule<O, R> {
+    fn try_fix_data(&self, ops: O, value: O::T, ctx: &mut Context) -> DataResult<O::T> {
+        self.rule.try_revert_data(ops, value, ctx)
+    }
+
+    fn fix_data(&self, ops: O, value: O::T) -> O::T {
+        self.rule.revert_data(ops, value)
+    }
+
+    fn fix_type(&self, ty: Type) -> Type {
+        self.rule.revert_type(ty)
+    }
+
+    fn try_fix_type(&self, ty: Type) -> DataResult<Type> {
+        self.rule.try_revert_type(ty)
+    }
+}
+
+impl<O: CodecOps, R: ReversibleRule<O>> ReversibleRule<O> for ReversedRule<O, R> {
+    fn try_revert_data(&self, ops: O, value: O::T, ctx: &mut Context) -> DataResult<O::T> {
+        self.rule.try_fix_data(ops, value, ctx)
+    }
+
+    fn revert_data(&self, ops: O, value: O::T) -> O::T {
+        self.rule.fix_data(ops, value)
+    }
+
+    fn revert_type(&self, ty: Type) -> Type {
+        self.rule.fix_type(ty)
+    }
+
+    fn try_revert_type(&self, ty: Type) -> DataResult<Type> {
+        self.rule.try_fix_type(ty)
+    }
+}
+
+/// One step of the path of [`Rules::at_segments`].
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum PathSegment {
+    /// The field with this name of an object.
+    Field(String),
+    /// The element at this index of a list.
+    Index(usize),
+    /// Values whose string field `tag` is `name`. Paths parsed from strings never contain this segment;
+    /// it is used by [`Prism::variant`](super::optics::Prism::variant).
+    Variant { tag: String, name: String },
+    /// Every element of a list.
+    Each,
+}
+
+impl PathSegment {
+    pub(crate) fn parse(path: &str) -> DataResult<Vec<PathSegment>> {
+        let mut segments = Vec::new();
+        for (index, part) in path.split('.').enumerate() {
+            let (name, mut rest) = part.split_at(part.find('[').unwrap_or(part.len()));
+            if !name.is_empty() {
+                segments.push(PathSegment::Field(name.to_string()));
+            } else if index != 0 || rest.is_empty() {
+                return Err(DataError::new_custom("path contains an empty field name"));
+            }
+            while !rest.is_empty() {
+                let Some(end) = rest.find(']').filter(|_| rest.starts_with('[')) else {
+                    return Err(DataError::new_custom(
+                        "expected [n] or [*] after field name",
+                    ));
+                };
+                match &rest[1..end] {
+                    "*" => segments.push(PathSegment::Each),
+                    index => {
+                        segments.push(PathSegment::Index(index.parse().map_err(|_| {
+                            DataError::new_custom("list index must be a number or *")
+                        })?))
+                    }
+                }
+                rest = &rest[end + 1..];
+            }
+        }
+        Ok(segments)
+    }
+
+    /// Calls `f` on every value matching `path`. If `strict` is true, parts of the path that are not
+    /// present in the value are reported as errors, otherwise they are skipped.
+    /// Values that are another variant, have no tag or are not maps at all never match a [`PathSegment::Variant`],
+    /// and are not errors.
+    pub(crate) fn walk_data<O: CodecOps>(
+        path: &[PathSegment],
+        ops: &O,
+        value: &mut O::T,
+        ctx: &mut Context,
+        strict: bool,
+        f: &dyn Fn(O, O::T, &mut Context) -> DataResult<O::T>,
+    ) -> DataResult<()> {
+        let Some((segment, rest)) = path.split_first() else {
+            *value = f(ops.clone(), value.clone(), ctx)?;
+            return Ok(());
+        };
+        match segment {
+            PathSegment::Field(name) => {
+                let field = ops.get_map_mut(value).and_then(|mut map| {
+                    Self::walk_field(&mut map, name, rest, ops, ctx, strict, f)
+                });
+                match field {
+                    Err(e) if strict => return Err(e),
+                    _ => {}
+                }
+            }
+            PathSegment::Index(index) => {
+                let element = ops.get_list_mut(value).and_then(|mut list| {
+                    ctx.push_array(*index);
+                    Self::walk_data(rest, ops, list.get_mut(*index)?, ctx, strict, f)?;
+                    ctx.pop();
+                    Ok(())
+                });
+                match element {
+                    Err(e) if strict => return Err(e),
+                    _ => {}
+                }
+            }
+            PathSegment::Variant { tag, name } => {
+                if Self::is_variant(ops, value, tag, name) {
+                    Self::walk_data(rest, ops, value, ctx, strict, f)?;
+                }
+            }
+            PathSegment::Each => match ops.get_list_mut(value) {
+                Ok(mut list) => {
+                    let mut index = 0;
+                    while let Ok(element) = list.get_mut(index) {
+                        ctx.push_array(index);
+                        Self::walk_data(rest, ops, element, ctx, strict, f)?;
+                        ctx.pop();
+                        index += 1;
+                    }
+                }
+                Err(e) if strict => return Err(e),
+                Err(_) => {}
+            },
+        }
+        Ok(())
+    }
+
+    /// Collects a copy of every value matching `path`, in the same way as [`PathSegment::walk_data`] with `strict` set,
+    /// but without modifying `value`.
+    pub(crate) fn collect<O: CodecOps>(
+        path: &[PathSegment],
+        ops: &O,
+        value: &O::T,
+        ctx: &mut Context,
+        matches: &mut Vec<O::T>,
+    ) -> DataResult<()> {
+        let Some((segment, rest)) = path.split_first() else {
+            matches.push(value.clone());
+            return Ok(());
+        };
+        match segment {
+            PathSegment::Field(name) => {
+                let map = ops.get_map(value)?;
+                ctx.push_field(name);
+                Self::collect(rest, ops, map.get(name)?, ctx, matches)?;
+                ctx.pop();
+            }
+            PathSegment::Index(index) => {
+                let list = ops.get_list(value)?;
+                ctx.push_array(*index);
+                Self::collect(rest, ops, list.get(*index)?, ctx, matches)?;
+                ctx.pop();
+            }
+            PathSegment::Variant { tag, name } => {
+                if Self::is_variant(ops, value, tag, name) {
+                    Self::collect(rest, ops, value, ctx, matches)?;
+                }
+            }
+            PathSegment::Each => {
+                let list = ops.get_list(value)?;
+                let mut index = 0;
+                while let Ok(element) = list.get(index) {
+                    ctx.push_array(index);
+                    Self::collect(rest, ops, element, ctx, matches)?;
+                    ctx.pop();
+                    index += 1;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn is_variant<O: CodecOps>(ops: &O, value: &O::T, tag: &str, name: &str) -> bool {
+        read_tag(ops, value, tag, &mut Context::new()).is_ok_and(|variant| variant == name)
+    }
+
+    fn walk_field<O: CodecOps>(
+        map: &mut impl MapViewMut<O::T>,
+        name: &str,
+        rest: &[PathSegment],
+        ops: &O,
+        ctx: &mut Context,
+        strict: bool,
+        f: &dyn Fn(O, O::T, &mut Context) -> DataResult<O::T>,
+    ) -> DataResult<()> {
+        ctx.push_field(name);
+        Self::walk_data(rest, ops, map.get_mut(name)?, ctx, strict, f)?;
+        ctx.pop();
+        Ok(())
+    }
+
+    /// Applies `f` to the type at `path`. Optional and recursive types are looked through, so a path
+    /// reaches the type of the value that is present in the data. If `strict` is true, parts of the path
+    /// that are not present in the type are reported as errors, otherwise the type is left as is.
+    pub(crate) fn walk_type(
+        path: &[PathSegment],
+        mut ty: Type,
+        strict: bool,
+        f: &dyn Fn(Type) -> DataResult<Type>,
+    ) -> DataResult<Type> {
+        match &ty {
+            Type::Optional(optional) => {
+                return Ok(Type::Optional(OptionalType::new(Self::walk_type(
+                    path,
+                    optional.ty().clone(),
+                    strict,
+                    f,
+                )?)));
+            }
+            Type::Recursive(recursive) => {
+                return Ok(Type::Recursive(RecursiveType::new(
+                    recursive.name(),
+                    Self::walk_type(path, recursive.ty().clone(), strict, f)?,
+                )));
+            }
+            _ => {}
+        }
+        let Some((segment, rest)) = path.split_first() else {
+            return f(ty);
+        };
+        match (segment, &mut ty) {
+            (PathSegment::Field(name), Type::Object(obj)) => {
+                match obj.remove(name) {
+                    Ok(field) => obj.insert(name, Self::walk_type(rest, field, strict, f)?),
+                    Err(e) if strict => return Err(e),
+                    Err(_) => {}
+                }
+                Ok(ty)
+            }
+            (PathSegment::Index(_) | PathSegment::Each, Type::Array(array)) => Ok(Type::Array(
+                ArrayType::new(Self::walk_type(rest, array.ty().clone(), strict, f)?),
+            )),
+            (PathSegment::Variant { tag, name }, Type::TaggedChoice(choice))
+                if choice.tag() == tag =>
+            {
+                match choice.remove_variant(name) {
+                    Ok(variant) => {
+                        choice.insert_variant(name, Self::walk_type(rest, variant, strict, f)?)
+                    }
+                    Err(e) if strict => return Err(e),
+                    Err(_) => {}
+                }
+                Ok(ty)
+            }
+            (_, Type::Unknown) => Ok(ty),
+            (PathSegment::Field(_), _) if strict => Err(DataError::unexpected_type("object")),
+            (PathSegment::Variant { tag, .. }, _) if strict => Err(DataError::unexpected_type(
+                &format!("tagged choice on {}", tag),
+            )),
+            (_, _) if strict => Err(DataError::unexpected_type("list")),
+            _ => Ok(ty),
+        }
+    }
+}
+
+/// Returns the object type `ty` describes, or `None` if `ty` is [`Type::Unknown`].
+fn expect_object(ty: &Type) -> DataResult<Option<&ObjectType>> {
+    match ty {
+        Type::Object(object) => Ok(Some(object)),
+        Type::Unknown => Ok(None),
+        _ => Err(DataError::unexpected_type("object")),
+    }
+}
+
+fn expect_object_mut(ty: &mut Type) -> DataResult<Option<&mut ObjectType>> {
+    match ty {
+        Type::Object(object) => Ok(Some(object)),
+        Type::Unknown => Ok(None),
+        _ => Err(DataError::unexpected_type("object")),
+    }
+}
+
+pub struct AtPathRule<O: CodecOps, R: TypeRewriteRule<O>> {
+    pub(crate) path: Vec<PathSegment>,
+    pub(crate) rule: R,
+    pub(crate) _phantom: PhantomData<O>,
+}
+
+impl<O: CodecOps, R: TypeRewriteRule<O>> TypeRewriteRule<O> for AtPathRule<O, R> {
+    fn try_fix_data(&self, ops: O, mut value: O::T, ctx: &mut Context) -> DataResult<O::T> {
+        PathSegment::walk_data(
+            &self.path,
+            &ops,
+            &mut value,
+            ctx,
+            true,
+            &|ops, value, ctx| self.rule.try_fix_data(ops, value, ctx),
+        )?;
+        Ok(value)
+    }
+
+    fn fix_data(&self, ops: O, mut value: O::T) -> O::T {
+        let _ = PathSegment::walk_data(
+            &self.path,
+            &ops,
+            &mut value,
+            &mut Context::new(),
+            false,
+            &|ops, value, _| Ok(self.rule.fix_data(ops, value)),
+        );
+        value
+    }
+
+    fn fix_type(&self, ty: Type) -> Type {
+        let fallback = ty.clone();
+        PathSegment::walk_type(&self.path, ty, false, &|ty| Ok(self.rule.fix_type(ty)))
+            .unwrap_or(fallback)
+    }
+
+    fn try_fix_type(&self, ty: Type) -> DataResult<Type> {
+        PathSegment::walk_type(&self.path, ty, true, &|ty| self.rule.try_fix_type(ty))
+    }
+}
+
+impl<O: CodecOps, R: ReversibleRule<O>> ReversibleRule<O> for AtPathRule<O, R> {
+    fn try_revert_data(&self, ops: O, mut value: O::T, ctx: &mut Context) -> DataResult<O::T> {
+        PathSegment::walk_data(
+            &self.path,
+            &ops,
+            &mut value,
+            ctx,
+            true,
+            &|ops, value, ctx| self.rule.try_revert_data(ops, value, ctx),
+        )?;
+        Ok(value)
+    }
+
+    fn revert_data(&self, ops: O, mut value: O::T) -> O::T {
+        let _ = PathSegment::walk_data(
+            &self.path,
+            &ops,
+            &mut value,
+            &mut Context::new(),
+            false,
+            &|ops, value, _| Ok(self.rule.revert_data(ops, value)),
+        );
+        value
+    }
+
+    fn revert_type(&self, ty: Type) -> Type {
+        let fallback = ty.clone();
+        PathSegment::walk_type(&self.path, ty, false, &|ty| Ok(self.rule.revert_type(ty)))
+            .unwrap_or(fallback)
+    }
+
+    fn try_revert_type(&self, ty: Type) -> DataResult<Type> {
+        PathSegment::walk_type(&self.path, ty, true, &|ty| self.rule.try_revert_type(ty))
+    }
+}
@@ -0,0 +1,145 @@
+use alloc::{
+    collections::btree_map::BTreeMap,
+    string::{String, ToString},
+    sync::Arc,
+};
+
+use crate::result::{DataError, DataResult};
+
+use super::Type;
+
+/// A [`TypeReference`] is a name for a kind of data, such as a player or an item.
+/// Rules can target a [`TypeReference`] instead of a raw value, and each [`Schema`] describes
+/// what [`Type`] the reference has in that version.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct TypeReference {
+    name: String,
+}
+
+impl TypeReference {
+    pub fn new(name: &str) -> Self {
+        TypeReference {
+            name: name.to_string(),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+/// A [`Schema`] maps each [`TypeReference`] to the [`Type`] it has in a specific version.
+///
+/// A [`Schema`] may have a parent schema. Any [`TypeReference`] that is not registered in this
+/// [`Schema`] is looked up in the parent instead, so a new version only needs to declare what changed.
+#[derive(Debug, Clone)]
+pub struct Schema {
+    version: u32,
+    parent: Option<Arc<Schema>>,
+    types: BTreeMap<TypeReference, Type>,
+    value_type: Option<Type>,
+}
+
+impl Schema {
+    pub fn new(version: u32) -> Self {
+        Schema {
+            version,
+            parent: None,
+            types: BTreeMap::new(),
+            value_type: None,
+        }
+    }
+
+    pub fn with_parent(version: u32, parent: Arc<Schema>) -> Self {
+        Schema {
+            version,
+            parent: Some(parent),
+            types: BTreeMap::new(),
+            value_type: None,
+        }
+    }
+
+    pub fn version(&self) -> u32 {
+        self.version
+    }
+
+    pub fn parent(&self) -> Option<&Schema> {
+        self.parent.as_deref()
+    }
+
+    pub(crate) fn set_parent(&mut self, parent: Arc<Schema>) {
+        self.parent = Some(parent);
+    }
+
+    /// Returns the [`TypeReference`]s registered in this version, not including those inherited from the parent.
+    pub fn references(&self) -> impl Iterator<Item = &TypeReference> {
+        self.types.keys()
+    }
+
+    /// Registers the [`Type`] of a [`TypeReference`] in this version, replacing the type inherited from the parent.
+    pub fn register_type(mut self, reference: &TypeReference, ty: Type) -> Self {
+        self.types.insert(reference.clone(), ty);
+        self
+    }
+
+    /// Registers the [`Type`] of the whole values upgraded by `DataFixer::update` in this version, replacing the
+    /// type inherited from the parent. Rules that are not attached to a [`TypeReference`] are checked against it.
+    pub fn register_value_type(mut self, ty: Type) -> Self {
+        self.value_type = Some(ty);
+        self
+    }
+
+    /// Returns the [`Type`] of whole values in this version, looking through the parent schemas if needed.
+    /// Returns `None` if no schema in the chain registers one.
+    pub fn value_type(&self) -> Option<Type> {
+        match &self.value_type {
+            Some(ty) => Some(ty.clone()),
+            None => self.parent.as_ref().and_then(|parent| parent.value_type()),
+        }
+    }
+
+    /// Returns the [`Type`] of a [`TypeReference`] in this version, looking through the parent schemas if needed.
+    /// May return a DataError::KeyNotFoundInMap if no schema in the chain registers the reference.
+    pub fn get_type(&self, reference: &TypeReference) -> DataResult<Type> {
+        match self.types.get(reference) {
+            Some(ty) => Ok(ty.clone()),
+            None => match &self.parent {
+                Some(parent) => parent.get_type(reference),
+                None => Err(DataError::key_not_found(reference.name())),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::sync::Arc;
+
+    use crate::fixers::{ObjectType, Type};
+
+    use super::{Schema, TypeReference};
+
+    #[test]
+    fn schema_inherits_types() {
+        let player = TypeReference::new("player");
+        let item = TypeReference::new("item");
+
+        let v1 = Arc::new(
+            Schema::new(1)
+                .register_type(
+                    &player,
+                    Type::Object(ObjectType::new().field("name", Type::String)),
+                )
+                .register_type(&item, Type::String),
+        );
+        let v2 = Schema::with_parent(2, v1).register_type(&item, Type::Int);
+
+        assert!(matches!(v2.get_type(&item), Ok(Type::Int)));
+        assert!(matches!(v2.get_type(&player), Ok(Type::Object(_))));
+        assert!(v2.get_type(&TypeReference::new("entity")).is_err());
+        assert!(matches!(
+            v2.parent().unwrap().get_type(&item),
+            Ok(Type::String)
+        ));
+    }
+}
@@ -1,13 +1,22 @@
 use alloc::{
     boxed::Box,
     collections::btree_map::BTreeMap,
+    format,
     string::{String, ToString},
+    vec::Vec,
 };
 
-use crate::result::{DataError, DataResult};
+use crate::{
+    result::{DataError, DataResult},
+    serialization::{CodecOps, Context, Dynamic, ListView, MapView, NumberKind, ValueKind},
+};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum Type {
+    /// A type that can't be determined ahead of time, such as the output of a codec that
+    /// picks its shape at runtime.
+    Unknown,
+
     Byte,
     Short,
     Int,
@@ -17,12 +26,26 @@ pub enum Type {
     Double,
 
     String,
+    Boolean,
+    Unit,
 
     Array(ArrayType),
     Object(ObjectType),
+    /// A map with arbitrary string keys, where every value has the same type.
+    Map(MapType),
+    /// A value that may be absent, such as an optional field of an object.
+    Optional(OptionalType),
+    /// A value that has one of two types, such as the result of a rule that only applies to some values.
+    Either(EitherType),
+    /// A map whose shape depends on the string value of a tag field.
+    TaggedChoice(TaggedChoiceType),
+    /// Binds a name to a type so it can refer to itself through [`Type::Named`].
+    Recursive(RecursiveType),
+    /// A reference to the type bound by an enclosing [`Type::Recursive`] with the same name.
+    Named(String),
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct ArrayType {
     ty: Box<Type>,
 }
@@ -37,7 +60,424 @@ impl ArrayType {
     }
 }
 
-#[derive(Debug, Clone)]
+impl Type {
+    /// Checks that `value` has the shape described by this type, returning every mismatch
+    /// along with the path it was found at, such as `$.a[3].b`. An empty list means the value is valid.
+    pub fn validate<O: CodecOps>(&self, value: &Dynamic<O>) -> Vec<(String, DataError)> {
+        let mut errors = Vec::new();
+        self.validate_value(
+            &value.ops(),
+            value.value(),
+            &mut Context::new(),
+            &mut Vec::new(),
+            &mut errors,
+        );
+        errors
+    }
+
+    /// Returns true if both types describe the same shape, treating [`Type::Unknown`] as matching any type.
+    pub fn is_compatible(&self, other: &Type) -> bool {
+        match (self, other) {
+            (Type::Unknown, _) | (_, Type::Unknown) => true,
+            (Type::Array(a), Type::Array(b)) => a.ty().is_compatible(b.ty()),
+            (Type::Map(a), Type::Map(b)) => a.ty().is_compatible(b.ty()),
+            (Type::Optional(a), Type::Optional(b)) => a.ty().is_compatible(b.ty()),
+            (Type::Either(a), Type::Either(b)) => {
+                a.left().is_compatible(b.left()) && a.right().is_compatible(b.right())
+            }
+            (Type::Recursive(a), Type::Recursive(b)) => {
+                a.name() == b.name() && a.ty().is_compatible(b.ty())
+            }
+            (Type::Object(a), Type::Object(b)) => {
+                a.fields.len() == b.fields.len()
+                    && a.fields().all(|(name, ty)| {
+                        b.fields
+                            .get(name)
+                            .is_some_and(|other| ty.is_compatible(other))
+                    })
+            }
+            (Type::TaggedChoice(a), Type::TaggedChoice(b)) => {
+                a.tag() == b.tag()
+                    && a.variants.len() == b.variants.len()
+                    && a.variants().all(|(name, ty)| {
+                        b.variants
+                            .get(name)
+                            .is_some_and(|other| ty.is_compatible(other))
+                    })
+            }
+            _ => self == other,
+        }
+    }
+
+    /// Returns false if no value of `kind` can have this type. Integer types admit integers of any width,
+    /// since not every format keeps track of them, and floating point types admit any number.
+    /// [`Type::Boolean`] admits numbers for formats that store booleans as bytes.
+    pub fn admits(&self, kind: ValueKind) -> bool {
+        match self {
+            Type::Unknown | Type::Named(_) => true,
+            Type::Byte | Type::Short | Type::Int | Type::Long => matches!(
+                kind,
+                ValueKind::Number(
+                    NumberKind::Byte | NumberKind::Short | NumberKind::Int | NumberKind::Long
+                )
+            ),
+            Type::Float | Type::Double => matches!(kind, ValueKind::Number(_)),
+            Type::String => kind == ValueKind::String,
+            Type::Boolean => matches!(kind, ValueKind::Bool | ValueKind::Number(_)),
+            Type::Unit => matches!(kind, ValueKind::Unit | ValueKind::Map),
+            Type::Array(_) => kind == ValueKind::List,
+            Type::Object(_) | Type::Map(_) | Type::TaggedChoice(_) => kind == ValueKind::Map,
+            Type::Optional(optional) => kind == ValueKind::Null || optional.ty().admits(kind),
+            Type::Either(either) => either.left().admits(kind) || either.right().admits(kind),
+            Type::Recursive(recursive) => recursive.ty().admits(kind),
+        }
+    }
+
+    fn describe(&self) -> &'static str {
+        match self {
+            Type::Unknown => "unknown",
+            Type::Byte => "byte",
+            Type::Short => "short",
+            Type::Int => "int",
+            Type::Long => "long",
+            Type::Float => "float",
+            Type::Double => "double",
+            Type::String => "string",
+            Type::Boolean => "boolean",
+            Type::Unit => "unit",
+            Type::Array(_) => "array",
+            Type::Object(_) => "object",
+            Type::Map(_) => "map",
+            Type::Optional(_) => "optional",
+            Type::Either(_) => "either",
+            Type::TaggedChoice(_) => "tagged choice",
+            Type::Recursive(_) => "recursive",
+            Type::Named(_) => "named",
+        }
+    }
+
+    /// Checks that an integer fits in `I`, since formats that don't track widths may truncate it when read as `I`.
+    fn check_integer<I: TryFrom<i64>, O: CodecOps>(&self, ops: &O, value: &O::T) -> DataResult<()> {
+        let value = ops.get_long(value)?;
+        I::try_from(value).map(|_| ()).map_err(|_| {
+            DataError::new_custom(&format!(
+                "{} is out of range for {}",
+                value,
+                self.describe()
+            ))
+        })
+    }
+
+    fn validate_value<'a, O: CodecOps>(
+        &'a self,
+        ops: &O,
+        value: &O::T,
+        ctx: &mut Context,
+        bindings: &mut Vec<&'a RecursiveType>,
+        errors: &mut Vec<(String, DataError)>,
+    ) {
+        let kind = ops.kind(value);
+        if !self.admits(kind) {
+            errors.push((
+                ctx.path(),
+                DataError::new_custom(&format!("expected {}, found {:?}", self.describe(), kind)),
+            ));
+            return;
+        }
+        let result = match self {
+            Type::Unknown => Ok(()),
+            Type::Byte => self.check_integer::<i8, O>(ops, value),
+            Type::Short => self.check_integer::<i16, O>(ops, value),
+            Type::Int => self.check_integer::<i32, O>(ops, value),
+            Type::Long => ops.get_long(value).map(|_| ()),
+            Type::Float => ops.get_float(value).map(|_| ()),
+            Type::Double => ops.get_double(value).map(|_| ()),
+            Type::String => ops.get_string(value).map(|_| ()),
+            Type::Boolean => ops.get_boolean(value).map(|_| ()),
+            Type::Unit => ops.get_unit(value),
+            Type::Array(array) => ops.get_list(value).map(|list| {
+                for (index, element) in list.into_iter().enumerate() {
+                    ctx.push_array(index);
+                    array
+                        .ty()
+                        .validate_value(ops, &element, ctx, bindings, errors);
+                    ctx.pop();
+                }
+            }),
+            Type::Object(object) => {
+                Self::validate_fields(object, None, ops, value, ctx, bindings, errors);
+                Ok(())
+            }
+            Type::Map(map_type) => ops.get_map(value).map(|map| {
+                for key in map.keys() {
+                    if let Ok(field) = map.get(&key) {
+                        ctx.push_field(&key);
+                        map_type
+                            .ty()
+                            .validate_value(ops, field, ctx, bindings, errors);
+                        ctx.pop();
+                    }
+                }
+            }),
+            Type::Optional(_) if kind == ValueKind::Null => Ok(()),
+            Type::Optional(optional) => {
+                optional
+                    .ty()
+                    .validate_value(ops, value, ctx, bindings, errors);
+                Ok(())
+            }
+            Type::Either(either) => {
+                let mut left = Vec::new();
+                either
+                    .left()
+                    .validate_value(ops, value, ctx, bindings, &mut left);
+                let mut right = Vec::new();
+                either
+                    .right()
+                    .validate_value(ops, value, ctx, bindings, &mut right);
+                if !left.is_empty() && !right.is_empty() {
+                    errors.extend(if left.len() <= right.len() {
+                        left
+                    } else {
+                        right
+                    });
+                }
+                Ok(())
+            }
+            Type::TaggedChoice(choice) => {
+                Self::validate_choice(choice, ops, value, ctx, bindings, errors);
+                Ok(())
+            }
+            Type::Recursive(recursive) => {
+                bindings.push(recursive);
+                recursive
+                    .ty()
+                    .validate_value(ops, value, ctx, bindings, errors);
+                bindings.pop();
+                Ok(())
+            }
+            Type::Named(name) => {
+                match bindings.iter().rev().find(|binding| binding.name() == name) {
+                    Some(binding) => {
+                        binding
+                            .ty()
+                            .validate_value(ops, value, ctx, bindings, errors);
+                        Ok(())
+                    }
+                    None => Err(DataError::new_custom(&format!(
+                        "type {name} is not bound by an enclosing recursive type"
+                    ))),
+                }
+            }
+        };
+        if let Err(error) = result {
+            errors.push((ctx.path(), error));
+        }
+    }
+
+    fn validate_fields<'a, O: CodecOps>(
+        object: &'a ObjectType,
+        tag: Option<&str>,
+        ops: &O,
+        value: &O::T,
+        ctx: &mut Context,
+        bindings: &mut Vec<&'a RecursiveType>,
+        errors: &mut Vec<(String, DataError)>,
+    ) {
+        let map = match ops.get_map(value) {
+            Ok(map) => map,
+            Err(error) => {
+                errors.push((ctx.path(), error));
+                return;
+            }
+        };
+        for (name, ty) in object.fields() {
+            ctx.push_field(name);
+            match map.get(name) {
+                Ok(field) => ty.validate_value(ops, field, ctx, bindings, errors),
+                Err(error) => {
+                    if !matches!(ty, Type::Optional(_)) {
+                        errors.push((ctx.path(), error));
+                    }
+                }
+            }
+            ctx.pop();
+        }
+        for key in map.keys() {
+            if object.get(&key).is_err() && tag != Some(key.as_str()) {
+                ctx.push_field(&key);
+                errors.push((
+                    ctx.path(),
+                    DataError::new_custom(&format!("unexpected field \"{key}\"")),
+                ));
+                ctx.pop();
+            }
+        }
+    }
+
+    fn validate_choice<'a, O: CodecOps>(
+        choice: &'a TaggedChoiceType,
+        ops: &O,
+        value: &O::T,
+        ctx: &mut Context,
+        bindings: &mut Vec<&'a RecursiveType>,
+        errors: &mut Vec<(String, DataError)>,
+    ) {
+        ctx.push_field(choice.tag());
+        let variant =
+            ops.get_map(value)
+                .and_then(|map| ops.get_string(map.get(choice.tag())?))
+                .and_then(|variant| {
+                    choice.variants.get(&variant).ok_or_else(|| {
+                        DataError::new_custom(&format!("unknown variant \"{variant}\""))
+                    })
+                });
+        let variant = match variant {
+            Ok(variant) => variant,
+            Err(error) => {
+                errors.push((ctx.path(), error));
+                ctx.pop();
+                return;
+            }
+        };
+        ctx.pop();
+        match variant {
+            Type::Object(object) => Self::validate_fields(
+                object,
+                Some(choice.tag()),
+                ops,
+                value,
+                ctx,
+                bindings,
+                errors,
+            ),
+            ty => ty.validate_value(ops, value, ctx, bindings, errors),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MapType {
+    ty: Box<Type>,
+}
+
+impl MapType {
+    pub fn new(ty: Type) -> Self {
+        MapType { ty: Box::new(ty) }
+    }
+
+    pub fn ty(&self) -> &Type {
+        &self.ty
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct OptionalType {
+    ty: Box<Type>,
+}
+
+impl OptionalType {
+    pub fn new(ty: Type) -> Self {
+        OptionalType { ty: Box::new(ty) }
+    }
+
+    pub fn ty(&self) -> &Type {
+        &self.ty
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecursiveType {
+    name: String,
+    ty: Box<Type>,
+}
+
+impl RecursiveType {
+    pub fn new(name: &str, ty: Type) -> Self {
+        RecursiveType {
+            name: name.to_string(),
+            ty: Box::new(ty),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn ty(&self) -> &Type {
+        &self.ty
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct EitherType {
+    left: Box<Type>,
+    right: Box<Type>,
+}
+
+impl EitherType {
+    pub fn new(left: Type, right: Type) -> Self {
+        EitherType {
+            left: Box::new(left),
+            right: Box::new(right),
+        }
+    }
+
+    pub fn left(&self) -> &Type {
+        &self.left
+    }
+
+    pub fn right(&self) -> &Type {
+        &self.right
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TaggedChoiceType {
+    tag: String,
+    variants: BTreeMap<String, Type>,
+}
+
+impl TaggedChoiceType {
+    pub fn new(tag: &str) -> Self {
+        TaggedChoiceType {
+            tag: tag.to_string(),
+            variants: BTreeMap::new(),
+        }
+    }
+
+    pub fn tag(&self) -> &str {
+        &self.tag
+    }
+
+    pub fn get_variant(&self, name: &str) -> DataResult<Type> {
+        self.variants
+            .get(name)
+            .ok_or(DataError::key_not_found(name))
+            .cloned()
+    }
+
+    pub fn variants(&self) -> impl Iterator<Item = (&str, &Type)> {
+        self.variants.iter().map(|(name, ty)| (name.as_str(), ty))
+    }
+
+    pub fn variant(mut self, name: &str, ty: Type) -> Self {
+        self.variants.insert(name.to_string(), ty);
+        self
+    }
+
+    pub fn insert_variant(&mut self, name: &str, ty: Type) {
+        self.variants.insert(name.to_string(), ty);
+    }
+
+    pub fn remove_variant(&mut self, name: &str) -> DataResult<Type> {
+        self.variants
+            .remove(name)
+            .ok_or(DataError::key_not_found(name))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct ObjectType {
     fields: BTreeMap<String, Type>,
 }
@@ -49,6 +489,10 @@ impl ObjectType {
         }
     }
 
+    pub fn fields(&self) -> impl Iterator<Item = (&str, &Type)> {
+        self.fields.iter().map(|(name, ty)| (name.as_str(), ty))
+    }
+
     pub fn get(&self, field: &str) -> DataResult<Type> {
         self.fields
             .get(field)
@@ -77,3 +521,127 @@ impl Default for ObjectType {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use alloc::{string::String, vec::Vec};
+
+    use crate::serialization::{Dynamic, NumberKind, ValueKind, json::JsonOps};
+
+    use super::{ArrayType, ObjectType, OptionalType, RecursiveType, TaggedChoiceType, Type};
+
+    fn paths(errors: Vec<(String, crate::result::DataError)>) -> Vec<String> {
+        errors.into_iter().map(|(path, _)| path).collect()
+    }
+
+    #[test]
+    fn validate_reports_every_mismatch() {
+        let ty = Type::Object(
+            ObjectType::new()
+                .field("name", Type::String)
+                .field("nickname", Type::Optional(OptionalType::new(Type::String)))
+                .field(
+                    "items",
+                    Type::Array(ArrayType::new(Type::Object(
+                        ObjectType::new()
+                            .field("id", Type::Int)
+                            .field("count", Type::Int),
+                    ))),
+                ),
+        );
+
+        let valid =
+            json::parse(r#"{ "name": "Steve", "items": [ { "id": 1, "count": 2 } ] }"#).unwrap();
+        assert!(ty.validate(&Dynamic::new(valid, JsonOps)).is_empty());
+
+        let invalid = json::parse(
+            r#"{ "items": [ { "id": 1, "count": 2 }, { "id": "x" } ], "extra": true }"#,
+        )
+        .unwrap();
+        assert_eq!(
+            paths(ty.validate(&Dynamic::new(invalid, JsonOps))),
+            ["$.items[1].count", "$.items[1].id", "$.name", "$.extra"]
+        );
+    }
+
+    #[test]
+    fn validate_recursive_and_tagged_types() {
+        let list = Type::Recursive(RecursiveType::new(
+            "list",
+            Type::Object(ObjectType::new().field("value", Type::Int).field(
+                "next",
+                Type::Optional(OptionalType::new(Type::Named("list".into()))),
+            )),
+        ));
+        let value =
+            json::parse(r#"{ "value": 1, "next": { "value": 2, "next": { "value": "3" } } }"#)
+                .unwrap();
+        assert_eq!(
+            paths(list.validate(&Dynamic::new(value, JsonOps))),
+            ["$.next.next.value"]
+        );
+
+        let event = Type::TaggedChoice(
+            TaggedChoiceType::new("event")
+                .variant(
+                    "click",
+                    Type::Object(ObjectType::new().field("x", Type::Int)),
+                )
+                .variant("load", Type::Object(ObjectType::new())),
+        );
+        let click = json::parse(r#"{ "event": "click", "x": 1 }"#).unwrap();
+        assert!(event.validate(&Dynamic::new(click, JsonOps)).is_empty());
+        let unknown = json::parse(r#"{ "event": "scroll" }"#).unwrap();
+        assert_eq!(
+            paths(event.validate(&Dynamic::new(unknown, JsonOps))),
+            ["$.event"]
+        );
+    }
+
+    #[test]
+    fn validate_by_value_kind() {
+        let ty = Type::Object(
+            ObjectType::new()
+                .field("name", Type::String)
+                .field("nickname", Type::Optional(OptionalType::new(Type::String))),
+        );
+        let value = json::parse(r#"{ "name": 5, "nickname": null }"#).unwrap();
+        let errors = ty.validate(&Dynamic::new(value, JsonOps));
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].0, "$.name");
+        assert_eq!(
+            alloc::format!("{}", errors[0].1),
+            "expected string, found Number(Int)"
+        );
+
+        assert!(Type::Boolean.admits(ValueKind::Number(NumberKind::Byte)));
+        assert!(!Type::Int.admits(ValueKind::Null));
+        assert!(!Type::Int.admits(ValueKind::Number(NumberKind::Double)));
+        assert!(Type::Double.admits(ValueKind::Number(NumberKind::Int)));
+    }
+
+    #[test]
+    fn validate_number_widths() {
+        let ty = Type::Object(
+            ObjectType::new()
+                .field("level", Type::Int)
+                .field("b", Type::Byte)
+                .field("xp", Type::Double),
+        );
+        let valid = json::parse(r#"{ "level": 3, "b": -128, "xp": 2 }"#).unwrap();
+        assert!(ty.validate(&Dynamic::new(valid, JsonOps)).is_empty());
+
+        let invalid = json::parse(r#"{ "level": 1.5, "b": 100000, "xp": 0.5 }"#).unwrap();
+        let errors = ty.validate(&Dynamic::new(invalid, JsonOps));
+        assert_eq!(
+            errors
+                .iter()
+                .map(|(path, error)| alloc::format!("{path}: {error}"))
+                .collect::<Vec<_>>(),
+            [
+                "$.b: 100000 is out of range for byte",
+                "$.level: expected int, found Number(Double)"
+            ]
+        );
+    }
+}
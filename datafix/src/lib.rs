@@ -10,7 +10,8 @@
 //!   while Datafixer uses data & traits to generate the code needed.
 //!
 //! * **Data transformations**
-//!   `todo!()`
+//!   Datafixer upgrades serialized data between versions using [`fixers::TypeRewriteRule`]s,
+//!   which are chained together for each version inside of a [`fixers::DataFixer`].
 //!
 //!
 //! For example, let's say you're developing a text editor and want to provide a configuration.
@@ -40,7 +41,15 @@
 //! }
 //! ```
 //! You ideally want your user's configuration to be automatically updated with the new data and a sensible default.
-//! This part is currently `todo!()`, sorry. :(
+//! You can describe how to get from one version to the next using a [`fixers::DataFixer`].
+//! ```rs
+//! let fixer = DataFixerBuilder::new()
+//!     .add_version(1, Rules::new_field("exit_key", |ctx| ctx.create_string("Escape"), |_| Type::String))
+//!     .build()?;
+//!
+//! let upgraded = fixer.update_to_current(&JsonOps, old_config, 0)?;
+//! let config = Config::codec().decode_start(&JsonOps, &upgraded)?;
+//! ```
 #![no_std]
 
 extern crate alloc;
@@ -11,8 +11,11 @@ use alloc::{
 use either::Either;
 
 use crate::{
+    fixers::{ArrayType, DataFixer, EitherType, MapType, ObjectType, RecursiveType, Type},
     result::{DataError, DataResult},
-    serialization::{Codec, CodecAdapters, CodecOps, Context, DefaultCodec, ListView, MapView},
+    serialization::{
+        Codec, CodecAdapters, CodecOps, Context, DefaultCodec, ListView, MapView, MapViewMut,
+    },
 };
 
 #[derive(Clone, Debug)]
@@ -26,6 +29,10 @@ impl<O: CodecOps> Codec<String, O> for StringCodec {
     fn decode(&self, ops: &O, value: &O::T, _ctx: &mut Context) -> DataResult<String> {
         ops.get_string(value)
     }
+
+    fn schema(&self) -> Type {
+        Type::String
+    }
 }
 
 impl<O: CodecOps> DefaultCodec<O> for String {
@@ -45,6 +52,10 @@ impl<O: CodecOps> Codec<bool, O> for BoolCodec {
     fn decode(&self, ops: &O, value: &O::T, _ctx: &mut Context) -> DataResult<bool> {
         ops.get_boolean(value)
     }
+
+    fn schema(&self) -> Type {
+        Type::Boolean
+    }
 }
 
 impl<O: CodecOps> DefaultCodec<O> for bool {
@@ -80,6 +91,10 @@ impl<T, C: Codec<T, O>, O: CodecOps> Codec<Vec<T>, O> for ListCodec<T, C, O> {
         }
         Ok(vec)
     }
+
+    fn schema(&self) -> Type {
+        Type::Array(ArrayType::new(self.inner.schema()))
+    }
 }
 
 pub(crate) struct XMapCodec<OLT, NT, C, F1, F2, O: CodecOps>
@@ -107,6 +122,10 @@ where
     fn decode(&self, ops: &O, value: &O::T, ctx: &mut Context) -> DataResult<NT> {
         Ok((self.f1)(&self.inner.decode(ops, value, ctx)?))
     }
+
+    fn schema(&self) -> Type {
+        self.inner.schema()
+    }
 }
 
 pub(crate) struct FlatXMapCodec<OLT, NT, C, F1, F2, O: CodecOps>
@@ -134,6 +153,10 @@ where
     fn decode(&self, ops: &O, value: &O::T, ctx: &mut Context) -> DataResult<NT> {
         (self.f1)(&self.inner.decode(ops, value, ctx)?)
     }
+
+    fn schema(&self) -> Type {
+        self.inner.schema()
+    }
 }
 
 pub(crate) struct PairCodec<L, R, Lc: Codec<L, O>, Rc: Codec<R, O>, O: CodecOps> {
@@ -159,6 +182,14 @@ impl<L, R, Lc: Codec<L, O>, Rc: Codec<R, O>, O: CodecOps> Codec<(L, R), O>
         let p2 = self.right.decode(ops, right, ctx)?;
         Ok((p1, p2))
     }
+
+    fn schema(&self) -> Type {
+        Type::Object(
+            ObjectType::new()
+                .field("left", self.left.schema())
+                .field("right", self.right.schema()),
+        )
+    }
 }
 
 pub(crate) struct BoundedCodec<T: PartialOrd, C: Codec<T, O>, R: RangeBounds<T>, O: CodecOps> {
@@ -194,6 +225,10 @@ impl<T: PartialOrd + Debug, C: Codec<T, O>, R: RangeBounds<T>, O: CodecOps> Code
             )))
         }
     }
+
+    fn schema(&self) -> Type {
+        self.codec.schema()
+    }
 }
 
 pub struct DynamicCodec<T, O: CodecOps> {
@@ -208,6 +243,10 @@ impl<T, O: CodecOps> Codec<T, O> for DynamicCodec<T, O> {
     fn decode(&self, ops: &O, value: &O::T, ctx: &mut Context) -> DataResult<T> {
         self.codec.as_ref().decode(ops, value, ctx)
     }
+
+    fn schema(&self) -> Type {
+        self.codec.as_ref().schema()
+    }
 }
 
 pub struct ArcCodec<T, O: CodecOps> {
@@ -230,11 +269,16 @@ impl<T, O: CodecOps> Codec<T, O> for ArcCodec<T, O> {
     fn decode(&self, ops: &O, value: &O::T, ctx: &mut Context) -> DataResult<T> {
         self.codec.as_ref().decode(ops, value, ctx)
     }
+
+    fn schema(&self) -> Type {
+        self.codec.as_ref().schema()
+    }
 }
 
 pub struct FnCodec<T, O: CodecOps> {
     pub(crate) encode: Box<dyn Fn(&O, &T, &mut Context) -> DataResult<O::T>>,
     pub(crate) decode: Box<dyn Fn(&O, &O::T, &mut Context) -> DataResult<T>>,
+    pub(crate) schema: Type,
 }
 
 impl<T, O: CodecOps> Codec<T, O> for FnCodec<T, O> {
@@ -245,6 +289,37 @@ impl<T, O: CodecOps> Codec<T, O> for FnCodec<T, O> {
     fn decode(&self, ops: &O, value: &O::T, ctx: &mut Context) -> DataResult<T> {
         (self.decode)(ops, value, ctx)
     }
+
+    fn schema(&self) -> Type {
+        self.schema.clone()
+    }
+}
+
+/// The outer codec of [`Codecs::recursive`], binding the name that the inner self-references use.
+///
+/// [`Codecs::recursive`]: crate::serialization::Codecs::recursive
+pub(crate) struct RecursiveCodec<T, O: CodecOps, C: Codec<T, O>> {
+    pub(crate) name: String,
+    pub(crate) codec: C,
+    pub(crate) _phantom: PhantomData<fn() -> (T, O)>,
+}
+
+impl<T, O: CodecOps, C: Codec<T, O>> Codec<T, O> for RecursiveCodec<T, O, C> {
+    fn encode(&self, ops: &O, value: &T, ctx: &mut Context) -> DataResult<O::T> {
+        self.codec.encode(ops, value, ctx)
+    }
+
+    fn decode(&self, ops: &O, value: &O::T, ctx: &mut Context) -> DataResult<T> {
+        self.codec.decode(ops, value, ctx)
+    }
+
+    fn schema(&self) -> Type {
+        Type::Recursive(RecursiveType::new(&self.name, self.codec.schema()))
+    }
+
+    fn debug(&self) -> String {
+        self.codec.debug()
+    }
 }
 
 pub struct BoxCodec<T, O: CodecOps, C: Codec<T, O>> {
@@ -260,6 +335,10 @@ impl<T, O: CodecOps, C: Codec<T, O>> Codec<Box<T>, O> for BoxCodec<T, O, C> {
     fn decode(&self, ops: &O, value: &O::T, ctx: &mut Context) -> DataResult<Box<T>> {
         self.inner.decode(ops, value, ctx).map(|x| Box::new(x))
     }
+
+    fn schema(&self) -> Type {
+        self.inner.schema()
+    }
 }
 
 pub struct TryElseCodec<T, O: CodecOps, Lc: Codec<T, O>, Rc: Codec<T, O>> {
@@ -300,6 +379,10 @@ impl<T, O: CodecOps, Lc: Codec<T, O>, Rc: Codec<T, O>> Codec<T, O> for TryElseCo
     fn debug(&self) -> String {
         format!("({}) orelse ({})", self.lc.debug(), self.rc.debug())
     }
+
+    fn schema(&self) -> Type {
+        either_schema(self.lc.schema(), self.rc.schema())
+    }
 }
 
 pub struct EitherCodec<T, O: CodecOps, T2, Lc: Codec<T, O>, Rc: Codec<T2, O>> {
@@ -327,6 +410,10 @@ impl<T, O: CodecOps, T2, Lc: Codec<T, O>, Rc: Codec<T2, O>> Codec<Either<T, T2>,
             },
         }
     }
+
+    fn schema(&self) -> Type {
+        either_schema(self.lc.schema(), self.rc.schema())
+    }
 }
 
 pub struct OrElseCodec<T, O: CodecOps, C: Codec<T, O>, F: Fn() -> T> {
@@ -346,6 +433,70 @@ impl<T, O: CodecOps, C: Codec<T, O>, F: Fn() -> T> Codec<T, O> for OrElseCodec<T
             .decode(ops, value, ctx)
             .unwrap_or_else(|_| (self.default)()))
     }
+
+    fn schema(&self) -> Type {
+        self.codec.schema()
+    }
+}
+
+pub struct VersionedCodec<T, O: CodecOps, C: Codec<T, O>> {
+    pub(crate) codec: C,
+    pub(crate) field_name: String,
+    pub(crate) version: u32,
+    pub(crate) fixer: DataFixer<O>,
+    pub(crate) _phantom: PhantomData<fn() -> T>,
+}
+
+impl<T, O: CodecOps, C: Codec<T, O>> Codec<T, O> for VersionedCodec<T, O, C> {
+    fn encode(&self, ops: &O, value: &T, ctx: &mut Context) -> DataResult<O::T> {
+        let mut encoded = self.codec.encode(ops, value, ctx)?;
+        let version = i32::try_from(self.version).map_err(|_| {
+            DataError::new_custom(&format!("version {} does not fit in an int", self.version))
+        })?;
+        ops.get_map_mut(&mut encoded)?
+            .set(&self.field_name, ops.create_int(&version));
+        Ok(encoded)
+    }
+
+    fn decode(&self, ops: &O, value: &O::T, ctx: &mut Context) -> DataResult<T> {
+        let mut value = value.clone();
+        // Data written before it was versioned has no version field, so it is treated as the oldest version.
+        let version = match ops.get_map_mut(&mut value)?.remove(&self.field_name) {
+            Ok(version) => {
+                ctx.push_field(&self.field_name);
+                let version = ops.get_int(&version)?;
+                let version = u32::try_from(version).map_err(|_| {
+                    DataError::new_custom(&format!("version {} must not be negative", version))
+                })?;
+                let supported = self.version.max(self.fixer.current_version());
+                if version > supported {
+                    return Err(DataError::new_custom(&format!(
+                        "data version {} is newer than the supported version {}",
+                        version, supported
+                    )));
+                }
+                ctx.pop();
+                version
+            }
+            Err(_) => 0,
+        };
+        let value = self
+            .fixer
+            .update_with_context(ops, value, None, version, self.version, ctx)?;
+        self.codec.decode(ops, &value, ctx)
+    }
+
+    fn debug(&self) -> String {
+        format!("Versioned({}, {})", self.version, self.codec.debug())
+    }
+
+    fn schema(&self) -> Type {
+        let mut schema = self.codec.schema();
+        if let Type::Object(obj) = &mut schema {
+            obj.insert(&self.field_name, Type::Int);
+        }
+        schema
+    }
 }
 
 pub struct DispatchCodec<
@@ -408,11 +559,15 @@ impl<T: Clone + PartialEq + Debug, C: Codec<T, O>, O: CodecOps> Codec<T, O>
     fn debug(&self) -> String {
         format!("Constant({:?})", self.constant)
     }
+
+    fn schema(&self) -> Type {
+        self.codec.schema()
+    }
 }
 
 macro_rules! make_numeric_codec {
     (
-        $({$t:ty, $struct_name:ident, $get_name:ident, $make_name:ident})*
+        $({$t:ty, $struct_name:ident, $get_name:ident, $make_name:ident, $schema:ident})*
         $(;)?
     ) => {
         $(pub struct $struct_name<O: CodecOps> {
@@ -427,6 +582,10 @@ macro_rules! make_numeric_codec {
             fn decode(&self, ops: &O, value: &O::T, _ctx: &mut Context) -> DataResult<$t> {
                 ops.$get_name(value)
             }
+
+            fn schema(&self) -> Type {
+                Type::$schema
+            }
         }
 
         impl<O: CodecOps> DefaultCodec<O> for $t {
@@ -440,13 +599,13 @@ macro_rules! make_numeric_codec {
 }
 
 make_numeric_codec! {
-    {f32, F32Codec, get_float, create_float}
-    {f64, F64Codec, get_double, create_double}
+    {f32, F32Codec, get_float, create_float, Float}
+    {f64, F64Codec, get_double, create_double, Double}
 
-    {i8, I8Codec, get_byte, create_byte}
-    {i16, I16Codec, get_short, create_short}
-    {i32, I32Codec, get_int, create_int}
-    {i64, I64Codec, get_long, create_long}
+    {i8, I8Codec, get_byte, create_byte, Byte}
+    {i16, I16Codec, get_short, create_short, Short}
+    {i32, I32Codec, get_int, create_int, Int}
+    {i64, I64Codec, get_long, create_long, Long}
 }
 
 macro_rules! make_unsigned_codec {
@@ -470,6 +629,14 @@ make_unsigned_codec! {
     {i64; u64}
 }
 
+fn either_schema(left: Type, right: Type) -> Type {
+    if left == right {
+        left
+    } else {
+        Type::Either(EitherType::new(left, right))
+    }
+}
+
 pub struct UntypedMapCodec<T, O: CodecOps, C: Codec<T, O>> {
     codec: C,
     _phantom: PhantomData<(T, O, C)>,
@@ -496,6 +663,10 @@ impl<T, O: CodecOps, C: Codec<T, O>> Codec<BTreeMap<String, T>, O> for UntypedMa
         }
         Ok(map)
     }
+
+    fn schema(&self) -> Type {
+        Type::Map(MapType::new(self.codec.schema()))
+    }
 }
 
 impl<T: DefaultCodec<O>, O: CodecOps> DefaultCodec<O> for BTreeMap<String, T> {
@@ -514,13 +685,19 @@ mod tests {
         collections::btree_map::BTreeMap,
         string::{String, ToString},
         vec,
+        vec::Vec,
     };
     use json::JsonValue;
 
     use crate::{
+        fixers::{
+            ArrayType, DataFixerBuilder, EitherType, MapType, ObjectType, OptionalType, Rules,
+            Type, TypeRewriteRule,
+        },
         result::DataError,
         serialization::{
-            Codec, CodecAdapters, CodecOps, Codecs, DefaultCodec, MapCodecBuilder,
+            Codec, CodecAdapters, CodecOps, Codecs, DefaultCodec, Dynamic, MapCodecBuilder,
+            ValueKind,
             builtins::codecs::{ArcCodec, DynamicCodec},
             json::JsonOps,
         },
@@ -726,6 +903,83 @@ mod tests {
         let decoded = codec.decode_start(&JsonOps, &encoded).unwrap();
 
         assert_eq!(value, decoded);
+
+        let Type::Recursive(schema) = codec.schema() else {
+            panic!("expected a recursive type");
+        };
+        assert_eq!(
+            schema.ty(),
+            &Type::Object(ObjectType::new().field("value", Type::Int).field(
+                "next",
+                Type::Optional(OptionalType::new(Type::Named(schema.name().to_string())))
+            ))
+        );
+    }
+
+    #[test]
+    fn codec_schema() {
+        struct Player {
+            name: String,
+            health: f32,
+            scores: Vec<i64>,
+            nickname: Option<String>,
+            flags: BTreeMap<String, bool>,
+        }
+
+        let codec = MapCodecBuilder::new()
+            .field(String::codec().field_of("name", |p: &Player| &p.name))
+            .field(f32::codec().field_of("health", |p: &Player| &p.health))
+            .field(
+                i64::codec()
+                    .list_of()
+                    .field_of("scores", |p: &Player| &p.scores),
+            )
+            .field(String::codec().optional_field_of("nickname", |p: &Player| &p.nickname))
+            .field(BTreeMap::<String, bool>::codec().field_of("flags", |p: &Player| &p.flags))
+            .build(|name, health, scores, nickname, flags| Player {
+                name,
+                health,
+                scores,
+                nickname,
+                flags,
+            });
+
+        let expected = Type::Object(
+            ObjectType::new()
+                .field("name", Type::String)
+                .field("health", Type::Float)
+                .field("scores", Type::Array(ArrayType::new(Type::Long)))
+                .field("nickname", Type::Optional(OptionalType::new(Type::String)))
+                .field("flags", Type::Map(MapType::new(Type::Boolean))),
+        );
+        assert_eq!(Codec::<Player, JsonOps>::schema(&codec), expected);
+
+        // A rule chain upgrading older data should end up at the current codec's schema.
+        let old = Type::Object(
+            ObjectType::new()
+                .field("name", Type::String)
+                .field("hp", Type::Float)
+                .field("scores", Type::Array(ArrayType::new(Type::Long)))
+                .field("nickname", Type::Optional(OptionalType::new(Type::String))),
+        );
+        let rule = Rules::rename_field::<JsonOps>("hp", "health").and_then(Rules::new_field(
+            "flags",
+            |ctx: &Dynamic<JsonOps>| Dynamic::new(JsonOps.create_map([]), ctx.ops()),
+            |_| Type::Map(MapType::new(Type::Boolean)),
+        ));
+        assert_eq!(rule.fix_type(old), expected);
+
+        assert_eq!(
+            Codec::<_, JsonOps>::schema(&u8::codec().or_else(|| 0)),
+            Type::Byte
+        );
+        assert_eq!(
+            Codec::<_, JsonOps>::schema(&Codecs::either::<_, _, (), _, _, _>(
+                i32::codec(),
+                String::codec()
+            )),
+            Type::Either(EitherType::new(Type::Int, Type::String))
+        );
     }
 
     #[test]
@@ -771,14 +1025,10 @@ mod tests {
                         UnknownType::Number(_) => Ok(UnknownType::number_codec()),
                         UnknownType::String(_) => Ok(UnknownType::string_codec()),
                     },
-                    |ops: &O, value: &O::T| {
-                        if ops.get_string(value).is_ok() {
-                            Ok(UnknownType::string_codec())
-                        } else if ops.get_double(value).is_ok() {
-                            Ok(UnknownType::number_codec())
-                        } else {
-                            Err(DataError::unexpected_type("string | number"))
-                        }
+                    |ops: &O, value: &O::T| match ops.kind(value) {
+                        ValueKind::String => Ok(UnknownType::string_codec()),
+                        ValueKind::Number(_) => Ok(UnknownType::number_codec()),
+                        _ => Err(DataError::unexpected_type("string | number")),
                     },
                 )
             }
@@ -799,6 +1049,65 @@ mod tests {
         assert_eq!(value, decoded);
     }
 
+    #[test]
+    fn versioned_codec() {
+        #[derive(Clone, Debug, PartialEq)]
+        struct Config {
+            font_size: i32,
+            exit_key: String,
+        }
+
+        let fixer = DataFixerBuilder::new()
+            .add_version(
+                2,
+                Rules::new_field(
+                    "exit_key",
+                    |ctx| ctx.create_string("Escape"),
+                    |_| Type::String,
+                ),
+            )
+            .build()
+            .unwrap();
+
+        let codec = MapCodecBuilder::new()
+            .field(i32::codec().field_of("font_size", |c: &Config| &c.font_size))
+            .field(String::codec().field_of("exit_key", |c: &Config| &c.exit_key))
+            .build(|font_size, exit_key| Config {
+                font_size,
+                exit_key,
+            })
+            .versioned("data_version", 2, fixer);
+
+        let value = Config {
+            font_size: 12,
+            exit_key: "Q".to_string(),
+        };
+        let encoded = codec.encode_start(&JsonOps, &value).unwrap();
+        assert_eq!(encoded["data_version"], 2);
+        let decoded = codec.decode_start(&JsonOps, &encoded).unwrap();
+        assert_eq!(value, decoded);
+
+        let mut old = JsonValue::new_object();
+        let _ = old.insert("font_size", 12);
+        let _ = old.insert("data_version", 1);
+        let decoded = codec.decode_start(&JsonOps, &old).unwrap();
+        assert_eq!(
+            decoded,
+            Config {
+                font_size: 12,
+                exit_key: "Escape".to_string()
+            }
+        );
+
+        let mut unversioned = JsonValue::new_object();
+        let _ = unversioned.insert("font_size", 12);
+        assert_eq!(codec.decode_start(&JsonOps, &unversioned).unwrap(), decoded);
+
+        let mut newer = encoded.clone();
+        let _ = newer.insert("data_version", 3);
+        assert!(codec.decode_start(&JsonOps, &newer).is_err());
+    }
+
     #[test]
     fn untyped_map_codec() {
         let mut map = BTreeMap::new();
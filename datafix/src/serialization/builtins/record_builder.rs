@@ -186,7 +186,43 @@ impl_record_codec_builder! {
     next: codec10: MapCodec10 as P10[P10C; P10F; P10R]
 }
 
-impl_record_codec_builder_last! {
+impl_record_codec_builder! {
     type: MapCodec10,
-    fields: { codec1: P1[P1C; P1F; P1R], codec2: P2[P2C; P2F; P2R], codec3: P3[P3C; P3F; P3R], codec4: P4[P4C; P4F; P4R], codec5: P5[P5C; P5F; P5R], codec6: P6[P6C; P6F; P6R], codec7: P7[P7C; P7F; P7R], codec8: P8[P8C; P8F; P8R], codec9: P9[P9C; P9F; P9R], codec10: P10[P10C; P10F; P10R] }
+    fields: { codec1: P1[P1C; P1F; P1R], codec2: P2[P2C; P2F; P2R], codec3: P3[P3C; P3F; P3R], codec4: P4[P4C; P4F; P4R], codec5: P5[P5C; P5F; P5R], codec6: P6[P6C; P6F; P6R], codec7: P7[P7C; P7F; P7R], codec8: P8[P8C; P8F; P8R], codec9: P9[P9C; P9F; P9R], codec10: P10[P10C; P10F; P10R] },
+    next: codec11: MapCodec11 as P11[P11C; P11F; P11R]
+}
+
+impl_record_codec_builder! {
+    type: MapCodec11,
+    fields: { codec1: P1[P1C; P1F; P1R], codec2: P2[P2C; P2F; P2R], codec3: P3[P3C; P3F; P3R], codec4: P4[P4C; P4F; P4R], codec5: P5[P5C; P5F; P5R], codec6: P6[P6C; P6F; P6R], codec7: P7[P7C; P7F; P7R], codec8: P8[P8C; P8F; P8R], codec9: P9[P9C; P9F; P9R], codec10: P10[P10C; P10F; P10R], codec11: P11[P11C; P11F; P11R] },
+    next: codec12: MapCodec12 as P12[P12C; P12F; P12R]
+}
+
+impl_record_codec_builder! {
+    type: MapCodec12,
+    fields: { codec1: P1[P1C; P1F; P1R], codec2: P2[P2C; P2F; P2R], codec3: P3[P3C; P3F; P3R], codec4: P4[P4C; P4F; P4R], codec5: P5[P5C; P5F; P5R], codec6: P6[P6C; P6F; P6R], codec7: P7[P7C; P7F; P7R], codec8: P8[P8C; P8F; P8R], codec9: P9[P9C; P9F; P9R], codec10: P10[P10C; P10F; P10R], codec11: P11[P11C; P11F; P11R], codec12: P12[P12C; P12F; P12R] },
+    next: codec13: MapCodec13 as P13[P13C; P13F; P13R]
+}
+
+impl_record_codec_builder! {
+    type: MapCodec13,
+    fields: { codec1: P1[P1C; P1F; P1R], codec2: P2[P2C; P2F; P2R], codec3: P3[P3C; P3F; P3R], codec4: P4[P4C; P4F; P4R], codec5: P5[P5C; P5F; P5R], codec6: P6[P6C; P6F; P6R], codec7: P7[P7C; P7F; P7R], codec8: P8[P8C; P8F; P8R], codec9: P9[P9C; P9F; P9R], codec10: P10[P10C; P10F; P10R], codec11: P11[P11C; P11F; P11R], codec12: P12[P12C; P12F; P12R], codec13: P13[P13C; P13F; P13R] },
+    next: codec14: MapCodec14 as P14[P14C; P14F; P14R]
+}
+
+impl_record_codec_builder! {
+    type: MapCodec14,
+    fields: { codec1: P1[P1C; P1F; P1R], codec2: P2[P2C; P2F; P2R], codec3: P3[P3C; P3F; P3R], codec4: P4[P4C; P4F; P4R], codec5: P5[P5C; P5F; P5R], codec6: P6[P6C; P6F; P6R], codec7: P7[P7C; P7F; P7R], codec8: P8[P8C; P8F; P8R], codec9: P9[P9C; P9F; P9R], codec10: P10[P10C; P10F; P10R], codec11: P11[P11C; P11F; P11R], codec12: P12[P12C; P12F; P12R], codec13: P13[P13C; P13F; P13R], codec14: P14[P14C; P14F; P14R] },
+    next: codec15: MapCodec15 as P15[P15C; P15F; P15R]
+}
+
+impl_record_codec_builder! {
+    type: MapCodec15,
+    fields: { codec1: P1[P1C; P1F; P1R], codec2: P2[P2C; P2F; P2R], codec3: P3[P3C; P3F; P3R], codec4: P4[P4C; P4F; P4R], codec5: P5[P5C; P5F; P5R], codec6: P6[P6C; P6F; P6R], codec7: P7[P7C; P7F; P7R], codec8: P8[P8C; P8F; P8R], codec9: P9[P9C; P9F; P9R], codec10: P10[P10C; P10F; P10R], codec11: P11[P11C; P11F; P11R], codec12: P12[P12C; P12F; P12R], codec13: P13[P13C; P13F; P13R], codec14: P14[P14C; P14F; P14R], codec15: P15[P15C; P15F; P15R] },
+    next: codec16: MapCodec16 as P16[P16C; P16F; P16R]
+}
+
+impl_record_codec_builder_last! {
+    type: MapCodec16,
+    fields: { codec1: P1[P1C; P1F; P1R], codec2: P2[P2C; P2F; P2R], codec3: P3[P3C; P3F; P3R], codec4: P4[P4C; P4F; P4R], codec5: P5[P5C; P5F; P5R], codec6: P6[P6C; P6F; P6R], codec7: P7[P7C; P7F; P7R], codec8: P8[P8C; P8F; P8R], codec9: P9[P9C; P9F; P9R], codec10: P10[P10C; P10F; P10R], codec11: P11[P11C; P11F; P11R], codec12: P12[P12C; P12F; P12R], codec13: P13[P13C; P13F; P13R], codec14: P14[P14C; P14F; P14R], codec15: P15[P15C; P15F; P15R], codec16: P16[P16C; P16F; P16R] }
 }
@@ -1,6 +1,7 @@
 use core::{cell::OnceCell, marker::PhantomData};
 
 use crate::{
+    fixers::{ObjectType, OptionalType, Type},
     result::{DataError, DataResult},
     serialization::{Codec, CodecOps, Context, MapView},
 };
@@ -16,6 +17,10 @@ pub trait MapFieldGetter<T, C: Codec<T, O>, Struct, Rt, O: CodecOps> {
     fn get_field(&self, ops: &O, value: &O::T, ctx: &mut Context) -> DataResult<Rt>;
     fn field_name(&self) -> &str;
     fn codec(&self) -> &C;
+    /// Returns the [`Type`] of this field's value, as it appears in an encoded record.
+    fn field_schema(&self) -> Type {
+        self.codec().schema()
+    }
 }
 
 pub struct OptionalField<T, C: Codec<T, O>, Struct, O: CodecOps> {
@@ -63,6 +68,10 @@ impl<T, C: Codec<T, O>, Struct, O: CodecOps> MapFieldGetter<T, C, Struct, Option
     fn codec(&self) -> &C {
         &self.codec
     }
+
+    fn field_schema(&self) -> Type {
+        Type::Optional(OptionalType::new(self.codec.schema()))
+    }
 }
 
 pub struct DefaultField<T, C: Codec<T, O>, Struct, O: CodecOps, F: Fn() -> T> {
@@ -203,6 +212,10 @@ impl<O: CodecOps> Codec<(), O> for UnitCodec {
     fn decode(&self, _ops: &O, _value: &O::T, _ctx: &mut Context) -> DataResult<()> {
         Ok(())
     }
+
+    fn schema(&self) -> Type {
+        Type::Unit
+    }
 }
 
 /// I'm sorry. Not even God himself understands this macro anymore.
@@ -266,6 +279,13 @@ macro_rules! record_codec {
                 str.push_str("]");
                 str
             }
+
+            fn schema(&self) -> Type {
+                Type::Object(
+                    ObjectType::new()
+                        $(.field(self.$field.field_name(), self.$field.field_schema()))*
+                )
+            }
         }
     };
 }
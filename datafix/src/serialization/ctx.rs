@@ -1,6 +1,6 @@
 use core::fmt::{Debug, Display};
 
-use alloc::{string::String, vec::Vec};
+use alloc::{format, string::String, vec::Vec};
 
 #[derive(Clone)]
 pub struct Context {
@@ -46,6 +46,14 @@ impl Context {
     pub fn pop_save(&mut self) -> Option<Context> {
         self.cache.pop()
     }
+
+    /// Returns the current location as a path such as `$.a[3].b`, without the `Stack trace: ` prefix.
+    pub fn path(&self) -> String {
+        self.stack_trace
+            .iter()
+            .map(|element| format!("{element:?}"))
+            .collect()
+    }
 }
 
 impl Default for Context {
@@ -95,6 +95,13 @@ impl<O: CodecOps> Dynamic<O> {
         }
     }
 
+    pub fn create_string(&self, value: &str) -> Self {
+        Dynamic {
+            value: self.ops.create_string(value),
+            ops: self.ops.clone(),
+        }
+    }
+
     pub fn get_field(&self, key: &str) -> DataResult<Self> {
         Ok(Dynamic::new(
             self.ops.get_map(&self.value)?.get(key).cloned()?,
@@ -13,7 +13,8 @@ use alloc::{
 use builtins::{
     codecs::{
         ArcCodec, BoundedCodec, BoxCodec, ConstantCodec, DispatchCodec, DynamicCodec, EitherCodec,
-        FlatXMapCodec, FnCodec, ListCodec, OrElseCodec, PairCodec, TryElseCodec, XMapCodec,
+        FlatXMapCodec, FnCodec, ListCodec, OrElseCodec, PairCodec, RecursiveCodec, TryElseCodec,
+        VersionedCodec, XMapCodec,
     },
     records::{DefaultField, FallibleField, OptionalField, RecordField, UnitCodec},
 };
@@ -24,7 +25,10 @@ pub use ctx::*;
 pub use dynamic::*;
 pub use ops::*;
 
-use crate::result::{CodecError, DataResult};
+use crate::{
+    fixers::{self, DataFixer},
+    result::{CodecError, DataResult},
+};
 pub use builtins::record_builder::MapCodecBuilder;
 
 /// A [`Codec<T>`] describes transformations to and from [`Dynamic`] for a type `T`.
@@ -66,6 +70,12 @@ pub trait Codec<Type, Ops: CodecOps> {
     fn debug(&self) -> String {
         pretty_type_name::pretty_type_name::<Self>().to_string()
     }
+
+    /// Returns the shape of the values this codec produces, so it can be compared against the
+    /// [`fixers::Type`] that rules rewrite. Codecs that can't know their shape ahead of time return [`fixers::Type::Unknown`].
+    fn schema(&self) -> fixers::Type {
+        fixers::Type::Unknown
+    }
 }
 
 /// Holds the adapter functions for [`Codec`] to allow codecs to do things such as:
@@ -215,6 +225,25 @@ where
         }
     }
 
+    /// Stores the data version in the field `field_name` of the encoded map.
+    /// When decoding, the stored version is read and the value is upgraded to `version` using `fixer`
+    /// before being decoded by this codec. Data without a version field is treated as version 0 and upgraded from there.
+    /// Data newer than `version` is downgraded if `fixer` knows how to, and fails to decode otherwise.
+    fn versioned(
+        self,
+        field_name: impl Into<String>,
+        version: u32,
+        fixer: DataFixer<O>,
+    ) -> impl Codec<T, O> {
+        VersionedCodec {
+            codec: self,
+            field_name: field_name.into(),
+            version,
+            fixer,
+            _phantom: PhantomData,
+        }
+    }
+
     /// Wraps this codec in a `Box<dyn Codec<...>>`, allowing it to be used in dynamic contexts where you
     /// only know which codec will be passed in at runtime. This also creates a pointer to a codec,
     /// enabling self-referential codecs.
@@ -263,6 +292,7 @@ pub struct Codecs;
 
 impl Codecs {
     /// Creates a [`Codec`] you can use for serializing and deserializing recursive types.
+    /// Its [`Codec::schema`] is a [`fixers::Type::Recursive`] named after `T`.
     ///
     /// For example, if you wanted to create a recursive codec for a linked list, you coudl do so with such:
     /// ```rs
@@ -287,6 +317,7 @@ impl Codecs {
     >(
         f: F,
     ) -> ArcCodec<T, O> {
+        let name = pretty_type_name::pretty_type_name::<T>().to_string();
         let placeholder = Rc::new(RefCell::new(None::<ArcCodec<_, _>>));
         let placeholder_clone_1 = placeholder.clone();
         let placeholder_clone_2 = placeholder.clone();
@@ -307,10 +338,16 @@ impl Codecs {
                         .expect("tried to decode before initialization")
                         .decode(ops, value, ctx)
                 }),
+                schema: fixers::Type::Named(name.clone()),
             }),
         };
 
-        let codec = f(dummy).arc();
+        let codec = RecursiveCodec {
+            name,
+            codec: f(dummy),
+            _phantom: PhantomData,
+        }
+        .arc();
 
         *placeholder.borrow_mut() = Some(codec.clone());
 
@@ -335,6 +372,8 @@ impl Codecs {
         }
     }
 
+    /// Picks the codec to use for each value. When decoding, `from_ops_to_codec` can branch on
+    /// [`CodecOps::kind`] to choose a codec without trying to decode the value first.
     pub fn dispatch<
         T,
         O: CodecOps,
@@ -0,0 +1,894 @@
+use alloc::{
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
+
+use crate::{
+    result::{DataError, DataResult},
+    serialization::{CodecOps, ListView, MapView},
+};
+
+use super::{ListViewMut, MapViewMut, NumberKind, ValueKind};
+
+/// A single CBOR data item, as described by RFC 8949.
+///
+/// Integers are written with the width of their variant, so they decode back into the same variant:
+/// a [`CborValue::Short`] is always written with a 2-byte argument, even if it would fit in less.
+/// [`CborValue::Float`]s are written as half-precision floats when that is lossless.
+/// Maps keep their keys in the order they were inserted, and only text keys are supported.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CborValue {
+    Byte(i8),
+    Short(i16),
+    Int(i32),
+    Long(i64),
+    Float(f32),
+    Double(f64),
+    Bool(bool),
+    Bytes(Vec<u8>),
+    Text(String),
+    Array(Vec<CborValue>),
+    Map(Vec<(String, CborValue)>),
+    /// Both `null` and `undefined` are read as [`CborValue::Null`].
+    Null,
+}
+
+const MAJOR_UNSIGNED: u8 = 0;
+const MAJOR_NEGATIVE: u8 = 1;
+const MAJOR_BYTES: u8 = 2;
+const MAJOR_TEXT: u8 = 3;
+const MAJOR_ARRAY: u8 = 4;
+const MAJOR_MAP: u8 = 5;
+const MAJOR_TAG: u8 = 6;
+const MAJOR_SIMPLE: u8 = 7;
+
+const INDEFINITE: u8 = 31;
+const BREAK: u8 = 0xFF;
+
+/// The deepest nesting of arrays, maps and tags that [`CborValue::from_bytes`] accepts.
+const MAX_DEPTH: usize = 512;
+
+impl CborValue {
+    /// Reads a single data item from `bytes`. Semantic tags are skipped, leaving only the tagged item.
+    /// Fails if there are bytes left over after the item, or if an integer doesn't fit in an `i64`.
+    pub fn from_bytes(bytes: &[u8]) -> DataResult<CborValue> {
+        let mut reader = CborReader { bytes, position: 0 };
+        let value = reader.read_value(0)?;
+        if reader.position != bytes.len() {
+            return Err(DataError::new_custom(&format!(
+                "{} trailing bytes after CBOR data item",
+                bytes.len() - reader.position
+            )));
+        }
+        Ok(value)
+    }
+
+    /// Writes this value as a single CBOR data item, using definite lengths.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        self.write(&mut out);
+        out
+    }
+
+    fn write(&self, out: &mut Vec<u8>) {
+        match self {
+            CborValue::Byte(value) => write_integer(out, *value as i64, 1),
+            CborValue::Short(value) => write_integer(out, *value as i64, 2),
+            CborValue::Int(value) => write_integer(out, *value as i64, 4),
+            CborValue::Long(value) => write_integer(out, *value, 8),
+            CborValue::Float(value) => match f32_to_f16(*value) {
+                Some(half) => {
+                    out.push(MAJOR_SIMPLE << 5 | 25);
+                    out.extend_from_slice(&half.to_be_bytes());
+                }
+                None => {
+                    out.push(MAJOR_SIMPLE << 5 | 26);
+                    out.extend_from_slice(&value.to_be_bytes());
+                }
+            },
+            CborValue::Double(value) => {
+                out.push(MAJOR_SIMPLE << 5 | 27);
+                out.extend_from_slice(&value.to_be_bytes());
+            }
+            CborValue::Bool(value) => out.push(MAJOR_SIMPLE << 5 | if *value { 21 } else { 20 }),
+            CborValue::Bytes(bytes) => {
+                write_head(out, MAJOR_BYTES, bytes.len() as u64, 0);
+                out.extend_from_slice(bytes);
+            }
+            CborValue::Text(text) => {
+                write_head(out, MAJOR_TEXT, text.len() as u64, 0);
+                out.extend_from_slice(text.as_bytes());
+            }
+            CborValue::Array(values) => {
+                write_head(out, MAJOR_ARRAY, values.len() as u64, 0);
+                for value in values {
+                    value.write(out);
+                }
+            }
+            CborValue::Map(entries) => {
+                write_head(out, MAJOR_MAP, entries.len() as u64, 0);
+                for (key, value) in entries {
+                    write_head(out, MAJOR_TEXT, key.len() as u64, 0);
+                    out.extend_from_slice(key.as_bytes());
+                    value.write(out);
+                }
+            }
+            CborValue::Null => out.push(MAJOR_SIMPLE << 5 | 22),
+        }
+    }
+
+    fn as_integer(&self) -> Option<i64> {
+        match self {
+            CborValue::Byte(value) => Some(*value as i64),
+            CborValue::Short(value) => Some(*value as i64),
+            CborValue::Int(value) => Some(*value as i64),
+            CborValue::Long(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    fn get_integer<I: TryFrom<i64>>(&self, expected: &str) -> DataResult<I> {
+        let value = self
+            .as_integer()
+            .ok_or_else(|| DataError::unexpected_type(expected))?;
+        I::try_from(value).map_err(|_| {
+            DataError::new_custom(&format!("{} is out of range for {}", value, expected))
+        })
+    }
+
+    fn as_number(&self) -> DataResult<f64> {
+        match self {
+            CborValue::Float(value) => Ok(*value as f64),
+            CborValue::Double(value) => Ok(*value),
+            _ => self
+                .as_integer()
+                .map(|value| value as f64)
+                .ok_or_else(|| DataError::unexpected_type("number")),
+        }
+    }
+
+    /// Expands a byte string into an array of bytes, leaving other values as they are.
+    fn expand_bytes(&mut self) -> bool {
+        let CborValue::Bytes(bytes) = self else {
+            return false;
+        };
+        *self = CborValue::Array(
+            bytes
+                .iter()
+                .map(|byte| CborValue::Byte(*byte as i8))
+                .collect(),
+        );
+        true
+    }
+
+    /// Packs an array of bytes back into a byte string if every element is still a byte.
+    fn pack_bytes(&mut self) {
+        let CborValue::Array(values) = self else {
+            return;
+        };
+        let bytes = values
+            .iter()
+            .map(|value| match value {
+                CborValue::Byte(byte) => Some(*byte as u8),
+                _ => None,
+            })
+            .collect::<Option<Vec<_>>>();
+        if let Some(bytes) = bytes {
+            *self = CborValue::Bytes(bytes);
+        }
+    }
+}
+
+/// Writes the initial byte and argument of a data item, using at least `width` bytes for the argument.
+fn write_head(out: &mut Vec<u8>, major: u8, argument: u64, width: usize) {
+    let major = major << 5;
+    if argument < 24 && width <= 1 {
+        out.push(major | argument as u8);
+    } else if argument <= u8::MAX as u64 && width <= 1 {
+        out.push(major | 24);
+        out.push(argument as u8);
+    } else if argument <= u16::MAX as u64 && width <= 2 {
+        out.push(major | 25);
+        out.extend_from_slice(&(argument as u16).to_be_bytes());
+    } else if argument <= u32::MAX as u64 && width <= 4 {
+        out.push(major | 26);
+        out.extend_from_slice(&(argument as u32).to_be_bytes());
+    } else {
+        out.push(major | 27);
+        out.extend_from_slice(&argument.to_be_bytes());
+    }
+}
+
+fn write_integer(out: &mut Vec<u8>, value: i64, width: usize) {
+    if value >= 0 {
+        write_head(out, MAJOR_UNSIGNED, value as u64, width);
+    } else {
+        write_head(out, MAJOR_NEGATIVE, !(value as u64), width);
+    }
+}
+
+/// Picks the narrowest variant that is at least as wide as the argument the integer was read from.
+fn read_integer(value: i128, width: usize) -> DataResult<CborValue> {
+    if width <= 1
+        && let Ok(value) = i8::try_from(value)
+    {
+        return Ok(CborValue::Byte(value));
+    }
+    if width <= 2
+        && let Ok(value) = i16::try_from(value)
+    {
+        return Ok(CborValue::Short(value));
+    }
+    if width <= 4
+        && let Ok(value) = i32::try_from(value)
+    {
+        return Ok(CborValue::Int(value));
+    }
+    i64::try_from(value)
+        .map(CborValue::Long)
+        .map_err(|_| DataError::new_custom(&format!("integer {} does not fit in an i64", value)))
+}
+
+fn f16_to_f32(bits: u16) -> f32 {
+    let sign = if bits & 0x8000 != 0 { -1.0 } else { 1.0 };
+    let exponent = ((bits >> 10) & 0x1F) as u32;
+    let mantissa = (bits & 0x3FF) as u32;
+    match exponent {
+        0 => sign * mantissa as f32 * (1.0 / 16_777_216.0),
+        0x1F => f32::from_bits(((bits as u32 & 0x8000) << 16) | (0xFF << 23) | (mantissa << 13)),
+        _ => f32::from_bits(
+            ((bits as u32 & 0x8000) << 16) | ((exponent + 112) << 23) | (mantissa << 13),
+        ),
+    }
+}
+
+/// Converts `value` to a half-precision float, if that can be done without losing precision.
+fn f32_to_f16(value: f32) -> Option<u16> {
+    if value.is_nan() {
+        return None;
+    }
+    let bits = value.to_bits();
+    let sign = ((bits >> 16) & 0x8000) as u16;
+    let exponent = ((bits >> 23) & 0xFF) as i32 - 127;
+    let mantissa = bits & 0x7F_FFFF;
+    let half = if value.is_infinite() {
+        sign | 0x7C00
+    } else if value == 0.0 {
+        sign
+    } else if (-14..=15).contains(&exponent) {
+        sign | (((exponent + 15) as u16) << 10) | (mantissa >> 13) as u16
+    } else if (-24..-14).contains(&exponent) {
+        sign | ((mantissa | 0x80_0000) >> (-exponent - 1)) as u16
+    } else {
+        return None;
+    };
+    (f16_to_f32(half).to_bits() == bits).then_some(half)
+}
+
+struct CborReader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> CborReader<'a> {
+    fn take(&mut self, count: usize) -> DataResult<&'a [u8]> {
+        let end = self
+            .position
+            .checked_add(count)
+            .filter(|end| *end <= self.bytes.len())
+            .ok_or_else(|| {
+                DataError::new_custom(&format!(
+                    "unexpected end of CBOR data at byte {}",
+                    self.position
+                ))
+            })?;
+        let slice = &self.bytes[self.position..end];
+        self.position = end;
+        Ok(slice)
+    }
+
+    fn take_array<const N: usize>(&mut self) -> DataResult<[u8; N]> {
+        let mut array = [0; N];
+        array.copy_from_slice(self.take(N)?);
+        Ok(array)
+    }
+
+    fn peek_break(&mut self) -> DataResult<bool> {
+        match self.bytes.get(self.position) {
+            Some(&BREAK) => {
+                self.position += 1;
+                Ok(true)
+            }
+            Some(_) => Ok(false),
+            None => Err(DataError::new_custom("unexpected end of CBOR data")),
+        }
+    }
+
+    /// Reads the initial byte and argument of a data item, returning the major type, the argument,
+    /// and how many bytes the argument took up. Indefinite lengths are returned as `None`.
+    fn read_head(&mut self) -> DataResult<(u8, Option<u64>, usize)> {
+        let initial = self.take(1)?[0];
+        let major = initial >> 5;
+        let info = initial & 0x1F;
+        Ok(match info {
+            0..24 => (major, Some(info as u64), 0),
+            24 => (major, Some(self.take(1)?[0] as u64), 1),
+            25 => (
+                major,
+                Some(u16::from_be_bytes(self.take_array()?) as u64),
+                2,
+            ),
+            26 => (
+                major,
+                Some(u32::from_be_bytes(self.take_array()?) as u64),
+                4,
+            ),
+            27 => (major, Some(u64::from_be_bytes(self.take_array()?)), 8),
+            INDEFINITE if matches!(major, MAJOR_BYTES..=MAJOR_MAP | MAJOR_SIMPLE) => {
+                (major, None, 0)
+            }
+            _ => {
+                return Err(DataError::new_custom(&format!(
+                    "invalid CBOR initial byte {:#04x}",
+                    initial
+                )));
+            }
+        })
+    }
+
+    /// Checks that a definite length could fit in the remaining input, where each element takes at least `width` bytes.
+    fn check_length(&self, length: u64, width: usize) -> DataResult<usize> {
+        usize::try_from(length)
+            .ok()
+            .filter(|length| length.saturating_mul(width) <= self.bytes.len() - self.position)
+            .ok_or_else(|| {
+                DataError::new_custom(&format!(
+                    "length {} is longer than the remaining CBOR data",
+                    length
+                ))
+            })
+    }
+
+    /// Reads the contents of a byte or text string, joining the chunks of indefinite-length strings.
+    fn read_string_bytes(&mut self, major: u8, length: Option<u64>) -> DataResult<Vec<u8>> {
+        match length {
+            Some(length) => {
+                let length = self.check_length(length, 1)?;
+                Ok(self.take(length)?.to_vec())
+            }
+            None => {
+                let mut bytes = Vec::new();
+                while !self.peek_break()? {
+                    let (chunk_major, chunk_length, _) = self.read_head()?;
+                    if chunk_major != major || chunk_length.is_none() {
+                        return Err(DataError::new_custom(
+                            "indefinite-length string chunks must be definite strings of the same type",
+                        ));
+                    }
+                    bytes.extend(self.read_string_bytes(major, chunk_length)?);
+                }
+                Ok(bytes)
+            }
+        }
+    }
+
+    fn read_value(&mut self, depth: usize) -> DataResult<CborValue> {
+        if depth > MAX_DEPTH {
+            return Err(DataError::new_custom(&format!(
+                "CBOR data is nested deeper than {} levels",
+                MAX_DEPTH
+            )));
+        }
+        let (major, argument, width) = self.read_head()?;
+        match (major, argument) {
+            (MAJOR_UNSIGNED, Some(value)) => read_integer(value as i128, width),
+            (MAJOR_NEGATIVE, Some(value)) => read_integer(-1 - value as i128, width),
+            (MAJOR_BYTES, length) => Ok(CborValue::Bytes(
+                self.read_string_bytes(MAJOR_BYTES, length)?,
+            )),
+            (MAJOR_TEXT, length) => String::from_utf8(self.read_string_bytes(MAJOR_TEXT, length)?)
+                .map(CborValue::Text)
+                .map_err(|_| DataError::new_custom("invalid UTF-8 in CBOR text string")),
+            (MAJOR_ARRAY, length) => {
+                let mut values = Vec::new();
+                match length {
+                    Some(length) => {
+                        for _ in 0..self.check_length(length, 1)? {
+                            values.push(self.read_value(depth + 1)?);
+                        }
+                    }
+                    None => {
+                        while !self.peek_break()? {
+                            values.push(self.read_value(depth + 1)?);
+                        }
+                    }
+                }
+                Ok(CborValue::Array(values))
+            }
+            (MAJOR_MAP, length) => {
+                let mut entries = Vec::new();
+                let mut read_entry = |reader: &mut Self| -> DataResult<()> {
+                    let CborValue::Text(key) = reader.read_value(depth + 1)? else {
+                        return Err(DataError::new_custom("CBOR map keys must be text strings"));
+                    };
+                    let value = reader.read_value(depth + 1)?;
+                    entries.push((key, value));
+                    Ok(())
+                };
+                match length {
+                    Some(length) => {
+                        for _ in 0..self.check_length(length, 2)? {
+                            read_entry(self)?;
+                        }
+                    }
+                    None => {
+                        while !self.peek_break()? {
+                            read_entry(self)?;
+                        }
+                    }
+                }
+                Ok(CborValue::Map(entries))
+            }
+            (MAJOR_TAG, _) => self.read_value(depth + 1),
+            (MAJOR_SIMPLE, Some(20)) if width == 0 => Ok(CborValue::Bool(false)),
+            (MAJOR_SIMPLE, Some(21)) if width == 0 => Ok(CborValue::Bool(true)),
+            (MAJOR_SIMPLE, Some(22 | 23)) if width == 0 => Ok(CborValue::Null),
+            (MAJOR_SIMPLE, Some(bits)) if width == 2 => {
+                Ok(CborValue::Float(f16_to_f32(bits as u16)))
+            }
+            (MAJOR_SIMPLE, Some(bits)) if width == 4 => {
+                Ok(CborValue::Float(f32::from_bits(bits as u32)))
+            }
+            (MAJOR_SIMPLE, Some(bits)) if width == 8 => Ok(CborValue::Double(f64::from_bits(bits))),
+            (MAJOR_SIMPLE, None) => Err(DataError::new_custom("unexpected CBOR break")),
+            _ => Err(DataError::new_custom(&format!(
+                "unsupported CBOR simple value {}",
+                argument.unwrap_or_default()
+            ))),
+        }
+    }
+}
+
+/// A [`CodecOps`] for [`CborValue`]s.
+///
+/// Like [`ValueOps`], integers can be read as any width they fit into, and floating point numbers can be read
+/// from any numeric value. Byte strings can be viewed and modified as lists of bytes.
+///
+/// [`ValueOps`]: super::value::ValueOps
+#[derive(Debug, Clone)]
+pub struct CborOps;
+
+impl CodecOps for CborOps {
+    type T = CborValue;
+
+    fn create_double(&self, value: &f64) -> CborValue {
+        CborValue::Double(*value)
+    }
+
+    fn create_float(&self, value: &f32) -> CborValue {
+        CborValue::Float(*value)
+    }
+
+    fn create_byte(&self, value: &i8) -> CborValue {
+        CborValue::Byte(*value)
+    }
+
+    fn create_short(&self, value: &i16) -> CborValue {
+        CborValue::Short(*value)
+    }
+
+    fn create_int(&self, value: &i32) -> CborValue {
+        CborValue::Int(*value)
+    }
+
+    fn create_long(&self, value: &i64) -> CborValue {
+        CborValue::Long(*value)
+    }
+
+    fn create_string(&self, value: &str) -> CborValue {
+        CborValue::Text(value.to_string())
+    }
+
+    fn create_boolean(&self, value: &bool) -> CborValue {
+        CborValue::Bool(*value)
+    }
+
+    fn create_list(&self, value: impl IntoIterator<Item = CborValue>) -> CborValue {
+        CborValue::Array(value.into_iter().collect())
+    }
+
+    fn create_map(&self, pairs: impl IntoIterator<Item = (String, CborValue)>) -> CborValue {
+        let mut map = CborValue::Map(Vec::new());
+        {
+            let mut view = CborMapViewMut { inner: &mut map };
+            for (key, value) in pairs {
+                view.set(&key, value);
+            }
+        }
+        map
+    }
+
+    fn create_unit(&self) -> CborValue {
+        CborValue::Map(Vec::new())
+    }
+
+    fn get_float(&self, value: &CborValue) -> DataResult<f32> {
+        match value {
+            CborValue::Float(value) => Ok(*value),
+            _ => value.as_number().map(|value| value as f32),
+        }
+    }
+
+    fn get_double(&self, value: &CborValue) -> DataResult<f64> {
+        value.as_number()
+    }
+
+    fn get_byte(&self, value: &CborValue) -> DataResult<i8> {
+        value.get_integer("byte")
+    }
+
+    fn get_short(&self, value: &CborValue) -> DataResult<i16> {
+        value.get_integer("short")
+    }
+
+    fn get_int(&self, value: &CborValue) -> DataResult<i32> {
+        value.get_integer("int")
+    }
+
+    fn get_long(&self, value: &CborValue) -> DataResult<i64> {
+        value.get_integer("long")
+    }
+
+    fn get_string(&self, value: &CborValue) -> DataResult<String> {
+        match value {
+            CborValue::Text(text) => Ok(text.clone()),
+            _ => Err(DataError::unexpected_type("text")),
+        }
+    }
+
+    fn get_boolean(&self, value: &CborValue) -> DataResult<bool> {
+        match value {
+            CborValue::Bool(boolean) => Ok(*boolean),
+            _ => Err(DataError::unexpected_type("boolean")),
+        }
+    }
+
+    fn get_list(&self, value: &CborValue) -> DataResult<impl ListView<CborValue>> {
+        match value {
+            CborValue::Array(values) => Ok(CborListView::Borrowed(values)),
+            CborValue::Bytes(bytes) => Ok(CborListView::Owned(
+                bytes
+                    .iter()
+                    .map(|byte| CborValue::Byte(*byte as i8))
+                    .collect(),
+            )),
+            _ => Err(DataError::unexpected_type("array")),
+        }
+    }
+
+    fn get_list_mut(&self, value: &mut CborValue) -> DataResult<impl ListViewMut<CborValue>> {
+        let bytes = value.expand_bytes();
+        match value {
+            CborValue::Array(_) => Ok(CborListViewMut {
+                inner: value,
+                bytes,
+            }),
+            _ => Err(DataError::unexpected_type("array")),
+        }
+    }
+
+    fn get_map(&self, value: &CborValue) -> DataResult<impl MapView<CborValue>> {
+        match value {
+            CborValue::Map(_) => Ok(CborMapView { inner: value }),
+            _ => Err(DataError::unexpected_type("map")),
+        }
+    }
+
+    fn get_map_mut(&self, value: &mut CborValue) -> DataResult<impl MapViewMut<CborValue>> {
+        match value {
+            CborValue::Map(_) => Ok(CborMapViewMut { inner: value }),
+            _ => Err(DataError::unexpected_type("map")),
+        }
+    }
+
+    fn get_unit(&self, value: &CborValue) -> DataResult<()> {
+        match value {
+            CborValue::Map(entries) if entries.is_empty() => Ok(()),
+            CborValue::Map(_) => Err(DataError::new_custom("map must have 0 fields")),
+            _ => Err(DataError::unexpected_type("map")),
+        }
+    }
+
+    fn kind(&self, value: &CborValue) -> ValueKind {
+        match value {
+            CborValue::Byte(_) => ValueKind::Number(NumberKind::Byte),
+            CborValue::Short(_) => ValueKind::Number(NumberKind::Short),
+            CborValue::Int(_) => ValueKind::Number(NumberKind::Int),
+            CborValue::Long(_) => ValueKind::Number(NumberKind::Long),
+            CborValue::Float(_) => ValueKind::Number(NumberKind::Float),
+            CborValue::Double(_) => ValueKind::Number(NumberKind::Double),
+            CborValue::Bool(_) => ValueKind::Bool,
+            CborValue::Text(_) => ValueKind::String,
+            CborValue::Bytes(_) | CborValue::Array(_) => ValueKind::List,
+            CborValue::Map(_) => ValueKind::Map,
+            CborValue::Null => ValueKind::Null,
+        }
+    }
+}
+
+fn map_get<'a>(value: &'a CborValue, name: &str) -> DataResult<&'a CborValue> {
+    let CborValue::Map(entries) = value else {
+        return Err(DataError::unexpected_type("map"));
+    };
+    entries
+        .iter()
+        .find(|(key, _)| key == name)
+        .map(|(_, value)| value)
+        .ok_or_else(|| DataError::key_not_found(name))
+}
+
+fn map_keys(value: &CborValue) -> Vec<String> {
+    let CborValue::Map(entries) = value else {
+        return Vec::new();
+    };
+    entries.iter().map(|(key, _)| key.clone()).collect()
+}
+
+struct CborMapView<'a> {
+    inner: &'a CborValue,
+}
+
+impl MapView<CborValue> for CborMapView<'_> {
+    fn get(&self, name: &str) -> DataResult<&CborValue> {
+        map_get(self.inner, name)
+    }
+
+    fn keys(&self) -> Vec<String> {
+        map_keys(self.inner)
+    }
+}
+
+struct CborMapViewMut<'a> {
+    inner: &'a mut CborValue,
+}
+
+impl MapView<CborValue> for CborMapViewMut<'_> {
+    fn get(&self, name: &str) -> DataResult<&CborValue> {
+        map_get(self.inner, name)
+    }
+
+    fn keys(&self) -> Vec<String> {
+        map_keys(self.inner)
+    }
+}
+
+impl MapViewMut<CborValue> for CborMapViewMut<'_> {
+    fn get_mut(&mut self, name: &str) -> DataResult<&mut CborValue> {
+        let CborValue::Map(entries) = self.inner else {
+            return Err(DataError::unexpected_type("map"));
+        };
+        entries
+            .iter_mut()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value)
+            .ok_or_else(|| DataError::key_not_found(name))
+    }
+
+    fn set(&mut self, name: &str, value: CborValue) {
+        let CborValue::Map(entries) = self.inner else {
+            return;
+        };
+        match entries.iter_mut().find(|(key, _)| key == name) {
+            Some((_, existing)) => *existing = value,
+            None => entries.push((name.to_string(), value)),
+        }
+    }
+
+    fn remove(&mut self, key: &str) -> DataResult<CborValue> {
+        let CborValue::Map(entries) = self.inner else {
+            return Err(DataError::unexpected_type("map"));
+        };
+        let index = entries
+            .iter()
+            .position(|(name, _)| name == key)
+            .ok_or_else(|| DataError::key_not_found(key))?;
+        Ok(entries.remove(index).1)
+    }
+}
+
+/// Byte strings don't hold [`CborValue`]s, so viewing one as a list copies its bytes out.
+enum CborListView<'a> {
+    Borrowed(&'a [CborValue]),
+    Owned(Vec<CborValue>),
+}
+
+impl ListView<CborValue> for CborListView<'_> {
+    fn get(&self, index: usize) -> DataResult<&CborValue> {
+        let values = match self {
+            CborListView::Borrowed(values) => *values,
+            CborListView::Owned(values) => values.as_slice(),
+        };
+        values
+            .get(index)
+            .ok_or(DataError::list_index_out_of_bounds(index, values.len()))
+    }
+
+    fn into_iter(self) -> impl Iterator<Item = CborValue> {
+        match self {
+            CborListView::Borrowed(values) => values.to_vec(),
+            CborListView::Owned(values) => values,
+        }
+        .into_iter()
+    }
+}
+
+/// A mutable view into an array. Byte strings are expanded into an array while the view is alive,
+/// and packed back into a byte string when it is dropped if every element is still a byte.
+struct CborListViewMut<'a> {
+    inner: &'a mut CborValue,
+    bytes: bool,
+}
+
+impl ListViewMut<CborValue> for CborListViewMut<'_> {
+    fn append(&mut self, value: CborValue) {
+        let CborValue::Array(values) = self.inner else {
+            return;
+        };
+        values.push(value);
+    }
+
+    fn get_mut(&mut self, index: usize) -> DataResult<&mut CborValue> {
+        let CborValue::Array(values) = self.inner else {
+            return Err(DataError::unexpected_type("array"));
+        };
+        let len = values.len();
+        values
+            .get_mut(index)
+            .ok_or(DataError::list_index_out_of_bounds(index, len))
+    }
+}
+
+impl Drop for CborListViewMut<'_> {
+    fn drop(&mut self) {
+        if self.bytes {
+            self.inner.pack_bytes();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{string::String, vec, vec::Vec};
+
+    use crate::serialization::{
+        Codec, CodecAdapters, CodecOps, DefaultCodec, ListViewMut, MapCodecBuilder,
+    };
+
+    use super::{CborOps, CborValue};
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct Reading {
+        sensor: String,
+        channel: i8,
+        sequence: i16,
+        count: i32,
+        timestamp: i64,
+        celsius: f32,
+        exact: f64,
+    }
+
+    impl<O: CodecOps> DefaultCodec<O> for Reading {
+        fn codec() -> impl Codec<Self, O> {
+            MapCodecBuilder::new()
+                .field(String::codec().field_of("sensor", |r: &Reading| &r.sensor))
+                .field(i8::codec().field_of("channel", |r: &Reading| &r.channel))
+                .field(i16::codec().field_of("sequence", |r: &Reading| &r.sequence))
+                .field(i32::codec().field_of("count", |r: &Reading| &r.count))
+                .field(i64::codec().field_of("timestamp", |r: &Reading| &r.timestamp))
+                .field(f32::codec().field_of("celsius", |r: &Reading| &r.celsius))
+                .field(f64::codec().field_of("exact", |r: &Reading| &r.exact))
+                .build(
+                    |sensor, channel, sequence, count, timestamp, celsius, exact| Reading {
+                        sensor,
+                        channel,
+                        sequence,
+                        count,
+                        timestamp,
+                        celsius,
+                        exact,
+                    },
+                )
+        }
+    }
+
+    #[test]
+    fn binary_round_trip() {
+        let reading = Reading {
+            sensor: "probe".into(),
+            channel: -3,
+            sequence: 7,
+            count: 100_000,
+            timestamp: -1,
+            celsius: 21.5,
+            exact: 0.1,
+        };
+        let encoded = Reading::codec().encode_start(&CborOps, &reading).unwrap();
+        let bytes = encoded.to_bytes();
+        let decoded = CborValue::from_bytes(&bytes).unwrap();
+        assert_eq!(decoded, encoded);
+        assert_eq!(
+            Reading::codec().decode_start(&CborOps, &decoded).unwrap(),
+            reading
+        );
+
+        assert!(CborValue::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+        assert!(CborValue::from_bytes(&[0xA1, 0x01, 0x02]).is_err());
+    }
+
+    #[test]
+    fn encoding_details() {
+        let cases: Vec<(CborValue, Vec<u8>)> = vec![
+            (CborValue::Byte(10), vec![0x0A]),
+            (CborValue::Byte(-100), vec![0x38, 0x63]),
+            (CborValue::Short(10), vec![0x19, 0x00, 0x0A]),
+            (CborValue::Int(-1), vec![0x3A, 0x00, 0x00, 0x00, 0x00]),
+            (CborValue::Float(1.5), vec![0xF9, 0x3E, 0x00]),
+            (CborValue::Float(5.960_464_5e-8), vec![0xF9, 0x00, 0x01]),
+            (
+                CborValue::Float(100_000.0),
+                vec![0xFA, 0x47, 0xC3, 0x50, 0x00],
+            ),
+            (
+                CborValue::Double(1.1),
+                vec![0xFB, 0x3F, 0xF1, 0x99, 0x99, 0x99, 0x99, 0x99, 0x9A],
+            ),
+            (CborValue::Bytes(vec![1, 2]), vec![0x42, 0x01, 0x02]),
+            (CborValue::Null, vec![0xF6]),
+        ];
+        for (value, bytes) in cases {
+            assert_eq!(value.to_bytes(), bytes);
+            assert_eq!(CborValue::from_bytes(&bytes).unwrap(), value);
+        }
+
+        // An unsigned byte that doesn't fit in an i8 is widened.
+        assert_eq!(
+            CborValue::from_bytes(&[0x18, 0xC8]).unwrap(),
+            CborValue::Short(200)
+        );
+        // Indefinite-length text, wrapped in a tag.
+        assert_eq!(
+            CborValue::from_bytes(&[
+                0xC0, 0x7F, 0x65, b's', b't', b'r', b'e', b'a', 0x64, b'm', b'i', b'n', b'g', 0xFF
+            ])
+            .unwrap(),
+            CborValue::Text("streaming".into())
+        );
+    }
+
+    #[test]
+    fn byte_strings_as_lists() {
+        let mut bytes = CborValue::Bytes(vec![1, 2]);
+        CborOps
+            .get_list_mut(&mut bytes)
+            .unwrap()
+            .append(CborValue::Byte(3));
+        assert_eq!(bytes, CborValue::Bytes(vec![1, 2, 3]));
+
+        let decoded = u8::codec()
+            .list_of()
+            .decode_start(&CborOps, &bytes)
+            .unwrap();
+        assert_eq!(decoded, vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn integer_ranges() {
+        assert_eq!(CborOps.get_byte(&CborValue::Long(-3)).unwrap(), -3);
+        assert_eq!(CborOps.get_short(&CborValue::Int(300)).unwrap(), 300);
+        assert!(CborOps.get_byte(&CborValue::Int(300)).is_err());
+        assert!(CborOps.get_int(&CborValue::Long(i64::MAX)).is_err());
+        assert!(CborOps.get_long(&CborValue::Double(1.5)).is_err());
+        assert_eq!(CborOps.get_double(&CborValue::Byte(2)).unwrap(), 2.0);
+    }
+}
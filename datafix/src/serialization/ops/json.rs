@@ -9,7 +9,7 @@ use crate::{
     serialization::{CodecOps, ListView, MapView},
 };
 
-use super::{ListViewMut, MapViewMut};
+use super::{ListViewMut, MapViewMut, NumberKind, ValueKind};
 
 #[derive(Debug, Clone)]
 pub struct JsonOps;
@@ -126,6 +126,17 @@ impl CodecOps for JsonOps {
         }
     }
 
+    fn kind(&self, value: &JsonValue) -> ValueKind {
+        match value {
+            JsonValue::Number(number) => ValueKind::Number(number_kind(number)),
+            JsonValue::String(_) | JsonValue::Short(_) => ValueKind::String,
+            JsonValue::Boolean(_) => ValueKind::Bool,
+            JsonValue::Array(_) => ValueKind::List,
+            JsonValue::Object(_) => ValueKind::Map,
+            JsonValue::Null => ValueKind::Null,
+        }
+    }
+
     fn create_float(&self, value: &f32) -> JsonValue {
         JsonValue::Number(Number::from(*value))
     }
@@ -182,6 +193,33 @@ impl CodecOps for JsonOps {
     }
 }
 
+/// JSON doesn't record numeric widths, so whole numbers are reported as the narrowest of
+/// [`NumberKind::Int`] and [`NumberKind::Long`] that fits them, and everything else as [`NumberKind::Double`].
+fn number_kind(number: &Number) -> NumberKind {
+    let (positive, mantissa, exponent) = number.as_parts();
+    if number.is_nan() || exponent < 0 {
+        return NumberKind::Double;
+    }
+    let whole = (0..exponent).try_fold(mantissa, |value, _| value.checked_mul(10));
+    // Widening to i128 keeps `i64::MIN`, whose magnitude doesn't fit in an i64, a whole number.
+    let Some(whole) = whole.map(|value| {
+        if positive {
+            value as i128
+        } else {
+            -(value as i128)
+        }
+    }) else {
+        return NumberKind::Double;
+    };
+    if i32::try_from(whole).is_ok() {
+        NumberKind::Int
+    } else if i64::try_from(whole).is_ok() {
+        NumberKind::Long
+    } else {
+        NumberKind::Double
+    }
+}
+
 struct JsonObjectView<'a> {
     inner: &'a JsonValue,
 }
@@ -1,8 +1,16 @@
+#[cfg(feature = "cbor")]
+pub mod cbor;
 pub mod json;
+pub mod nbt;
+pub mod value;
 
 use alloc::{string::String, vec::Vec};
 
-use crate::{fixers::TypeRewriteRule, result::DataResult};
+use crate::{
+    fixers::TypeRewriteRule,
+    result::{CodecError, CodecResult, DataResult},
+    serialization::Context,
+};
 
 /// A [`CodecOps`] represents a way of converting Rust values into the target datatype and vice-versa.
 /// [`CodecOps`] is the recommended way to do this when interacting with [`Codec`].
@@ -73,6 +81,16 @@ pub trait CodecOps: Clone {
     /// This converts a value of type `T` into a unit value with no fields or associated values.
     fn get_unit(&self, value: &Self::T) -> DataResult<()>;
 
+    /// Returns what kind of value `T` holds, without having to try each `get_*` method in turn.
+    /// Formats that can't tell a unit apart from an empty map should report [`ValueKind::Map`].
+    ///
+    /// By default, this tries each `get_*` method in turn, reporting whole numbers as the narrowest of
+    /// [`NumberKind::Int`] and [`NumberKind::Long`] that fits them and values no method accepts as [`ValueKind::Null`].
+    /// Formats that track numeric widths should override this.
+    fn kind(&self, value: &Self::T) -> ValueKind {
+        probe_kind(self, value)
+    }
+
     /// This purely exists for Optional Fields. The `Option` represents if a field is present,
     /// the `DataResult` represents the actual field data.
     fn create_map_special(
@@ -92,6 +110,99 @@ pub trait CodecOps: Clone {
     fn repair(&self, value: Self::T, rule: impl TypeRewriteRule<Self>) -> Self::T {
         rule.fix_data(self.clone(), value)
     }
+
+    fn try_repair(&self, value: Self::T, rule: impl TypeRewriteRule<Self>) -> CodecResult<Self::T> {
+        let mut ctx = Context::new();
+        rule.try_fix_data(self.clone(), value, &mut ctx)
+            .map_err(|e| CodecError::new(e, ctx))
+    }
+}
+
+/// The kind of a value held by a [`CodecOps`], as reported by [`CodecOps::kind`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValueKind {
+    Number(NumberKind),
+    String,
+    Bool,
+    List,
+    Map,
+    Unit,
+    /// An explicitly empty value, such as JSON's `null`.
+    Null,
+}
+
+/// The width of a numeric value. Formats that don't track widths should report the narrowest of
+/// [`NumberKind::Int`], [`NumberKind::Long`] or [`NumberKind::Double`] that holds the number exactly.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NumberKind {
+    Byte,
+    Short,
+    Int,
+    Long,
+    Float,
+    Double,
+}
+
+/// The default [`CodecOps::kind`], built from the `get_*` methods.
+fn probe_kind<O: CodecOps>(ops: &O, value: &O::T) -> ValueKind {
+    let double = ops.get_double(value);
+    if let Ok(long) = ops.get_long(value)
+        && double.as_ref().is_ok_and(|double| *double == long as f64)
+    {
+        ValueKind::Number(match i32::try_from(long) {
+            Ok(_) => NumberKind::Int,
+            Err(_) => NumberKind::Long,
+        })
+    } else if double.is_ok() {
+        ValueKind::Number(NumberKind::Double)
+    } else if ops.get_boolean(value).is_ok() {
+        ValueKind::Bool
+    } else if ops.get_string(value).is_ok() {
+        ValueKind::String
+    } else if ops.get_list(value).is_ok() {
+        ValueKind::List
+    } else if ops.get_map(value).is_ok() {
+        ValueKind::Map
+    } else if ops.get_unit(value).is_ok() {
+        ValueKind::Unit
+    } else {
+        ValueKind::Null
+    }
+}
+
+/// Converts a value from one [`CodecOps`] to another, such as turning a JSON document into NBT, without
+/// decoding it into a Rust type first. Numbers keep the width reported by [`CodecOps::kind`].
+///
+/// [`CodecOps`] has no way to create a null value, so [`ValueKind::Null`] becomes a unit.
+pub fn transcode<A: CodecOps, B: CodecOps>(from: &A, to: &B, value: &A::T) -> DataResult<B::T> {
+    Ok(match from.kind(value) {
+        ValueKind::Number(NumberKind::Byte) => to.create_byte(&from.get_byte(value)?),
+        ValueKind::Number(NumberKind::Short) => to.create_short(&from.get_short(value)?),
+        ValueKind::Number(NumberKind::Int) => to.create_int(&from.get_int(value)?),
+        ValueKind::Number(NumberKind::Long) => to.create_long(&from.get_long(value)?),
+        ValueKind::Number(NumberKind::Float) => to.create_float(&from.get_float(value)?),
+        ValueKind::Number(NumberKind::Double) => to.create_double(&from.get_double(value)?),
+        ValueKind::String => to.create_string(&from.get_string(value)?),
+        ValueKind::Bool => to.create_boolean(&from.get_boolean(value)?),
+        ValueKind::List => {
+            let list = from.get_list(value)?;
+            let mut elements = Vec::new();
+            while let Ok(element) = list.get(elements.len()) {
+                elements.push(transcode(from, to, element)?);
+            }
+            to.create_list(elements)
+        }
+        ValueKind::Map => {
+            let map = from.get_map(value)?;
+            let mut entries = Vec::new();
+            for key in map.keys() {
+                let entry = transcode(from, to, map.get(&key)?)?;
+                entries.push((key, entry));
+            }
+            to.create_map(entries)
+        }
+        ValueKind::Unit | ValueKind::Null => to.create_unit(),
+    })
 }
 
 /// Represents a lens into an map type from a [`CodecOps`].
@@ -137,3 +248,62 @@ pub trait ListViewMut<T> {
     /// This is up to the implementor of this method to check.
     fn get_mut(&mut self, index: usize) -> DataResult<&mut T>;
 }
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+
+    use super::{
+        CodecOps, MapView, NumberKind, ValueKind, json::JsonOps, nbt::NbtOps, nbt::NbtTag,
+        probe_kind, transcode, value::Value, value::ValueOps,
+    };
+
+    #[test]
+    fn default_kind_matches_json() {
+        let document = json::parse(
+            r#"[ 3, -9223372036854775808, 0.5, 1e300, "a", true, [], { "x": 1 }, {}, null ]"#,
+        )
+        .unwrap();
+        for value in document.members() {
+            assert_eq!(probe_kind(&JsonOps, value), JsonOps.kind(value), "{value}");
+        }
+        assert_eq!(
+            JsonOps.kind(&document[1]),
+            ValueKind::Number(NumberKind::Long)
+        );
+    }
+
+    #[test]
+    fn transcode_between_formats() {
+        let document =
+            json::parse(r#"{ "name": "Steve", "level": 3, "xp": 0.5, "big": 5000000000, "tags": [true, false] }"#)
+                .unwrap();
+        assert_eq!(
+            JsonOps.kind(&document["level"]),
+            ValueKind::Number(NumberKind::Int)
+        );
+
+        let value = transcode(&JsonOps, &ValueOps, &document).unwrap();
+        assert_eq!(
+            value,
+            Value::Map(vec![
+                ("name".into(), Value::String("Steve".into())),
+                ("level".into(), Value::Int(3)),
+                ("xp".into(), Value::Double(0.5)),
+                ("big".into(), Value::Long(5000000000)),
+                (
+                    "tags".into(),
+                    Value::List(vec![Value::Bool(true), Value::Bool(false)])
+                ),
+            ])
+        );
+
+        let nbt = transcode(&ValueOps, &NbtOps, &value).unwrap();
+        assert_eq!(
+            NbtOps.get_map(&nbt).unwrap().get("tags").unwrap(),
+            &NbtTag::ByteArray(vec![1, 0])
+        );
+        assert_eq!(transcode(&NbtOps, &NbtOps, &nbt).unwrap(), nbt);
+        assert_eq!(transcode(&ValueOps, &JsonOps, &value).unwrap(), document);
+    }
+}
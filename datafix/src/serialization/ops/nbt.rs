@@ -0,0 +1,880 @@
+use alloc::{
+    collections::BTreeMap,
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
+
+use crate::{
+    result::{DataError, DataResult},
+    serialization::{CodecOps, ListView, MapView},
+};
+
+use super::{ListViewMut, MapViewMut, NumberKind, ValueKind};
+
+/// A single value in Minecraft's Named Binary Tag format.
+///
+/// Booleans are stored as [`NbtTag::Byte`]s, and units as empty [`NbtTag::Compound`]s.
+#[derive(Debug, Clone, PartialEq)]
+pub enum NbtTag {
+    Byte(i8),
+    Short(i16),
+    Int(i32),
+    Long(i64),
+    Float(f32),
+    Double(f64),
+    ByteArray(Vec<i8>),
+    String(String),
+    List(Vec<NbtTag>),
+    Compound(BTreeMap<String, NbtTag>),
+    IntArray(Vec<i32>),
+    LongArray(Vec<i64>),
+}
+
+const TAG_END: u8 = 0;
+const TAG_BYTE: u8 = 1;
+const TAG_SHORT: u8 = 2;
+const TAG_INT: u8 = 3;
+const TAG_LONG: u8 = 4;
+const TAG_FLOAT: u8 = 5;
+const TAG_DOUBLE: u8 = 6;
+const TAG_BYTE_ARRAY: u8 = 7;
+const TAG_STRING: u8 = 8;
+const TAG_LIST: u8 = 9;
+const TAG_COMPOUND: u8 = 10;
+const TAG_INT_ARRAY: u8 = 11;
+const TAG_LONG_ARRAY: u8 = 12;
+
+/// The deepest nesting of lists and compounds that [`NbtTag::from_bytes`] accepts, matching Minecraft's own limit.
+const MAX_DEPTH: usize = 512;
+
+impl NbtTag {
+    /// Returns the numeric ID of this tag as it appears in the binary format.
+    pub fn id(&self) -> u8 {
+        match self {
+            NbtTag::Byte(_) => TAG_BYTE,
+            NbtTag::Short(_) => TAG_SHORT,
+            NbtTag::Int(_) => TAG_INT,
+            NbtTag::Long(_) => TAG_LONG,
+            NbtTag::Float(_) => TAG_FLOAT,
+            NbtTag::Double(_) => TAG_DOUBLE,
+            NbtTag::ByteArray(_) => TAG_BYTE_ARRAY,
+            NbtTag::String(_) => TAG_STRING,
+            NbtTag::List(_) => TAG_LIST,
+            NbtTag::Compound(_) => TAG_COMPOUND,
+            NbtTag::IntArray(_) => TAG_INT_ARRAY,
+            NbtTag::LongArray(_) => TAG_LONG_ARRAY,
+        }
+    }
+
+    /// Reads a named root tag from uncompressed, big-endian NBT data, such as the contents of a
+    /// `level.dat` file after it has been decompressed. Returns the name of the root tag alongside it.
+    pub fn from_bytes(bytes: &[u8]) -> DataResult<(String, NbtTag)> {
+        let mut reader = NbtReader { bytes, position: 0 };
+        let id = reader.read_u8()?;
+        if id == TAG_END {
+            return Err(DataError::new_custom("root tag can not be an end tag"));
+        }
+        let name = reader.read_string()?;
+        let tag = reader.read_payload(id, 0)?;
+        if reader.position != bytes.len() {
+            return Err(DataError::new_custom(&format!(
+                "{} trailing bytes after root tag",
+                bytes.len() - reader.position
+            )));
+        }
+        Ok((name, tag))
+    }
+
+    /// Writes this tag as a named root tag in uncompressed, big-endian NBT. Fails if a
+    /// [`NbtTag::List`] contains elements of different types, or a string is too long to be encoded.
+    pub fn to_bytes(&self, name: &str) -> DataResult<Vec<u8>> {
+        let mut out = Vec::new();
+        out.push(self.id());
+        write_string(&mut out, name)?;
+        self.write_payload(&mut out)?;
+        Ok(out)
+    }
+
+    fn write_payload(&self, out: &mut Vec<u8>) -> DataResult<()> {
+        match self {
+            NbtTag::Byte(value) => out.push(*value as u8),
+            NbtTag::Short(value) => out.extend_from_slice(&value.to_be_bytes()),
+            NbtTag::Int(value) => out.extend_from_slice(&value.to_be_bytes()),
+            NbtTag::Long(value) => out.extend_from_slice(&value.to_be_bytes()),
+            NbtTag::Float(value) => out.extend_from_slice(&value.to_be_bytes()),
+            NbtTag::Double(value) => out.extend_from_slice(&value.to_be_bytes()),
+            NbtTag::ByteArray(values) => {
+                write_length(out, values.len())?;
+                out.extend(values.iter().map(|value| *value as u8));
+            }
+            NbtTag::String(value) => write_string(out, value)?,
+            NbtTag::List(values) => {
+                let id = values.first().map(NbtTag::id).unwrap_or(TAG_END);
+                if values.iter().any(|value| value.id() != id) {
+                    return Err(DataError::new_custom(
+                        "list elements must all have the same tag type",
+                    ));
+                }
+                out.push(id);
+                write_length(out, values.len())?;
+                for value in values {
+                    value.write_payload(out)?;
+                }
+            }
+            NbtTag::Compound(entries) => {
+                for (key, value) in entries {
+                    out.push(value.id());
+                    write_string(out, key)?;
+                    value.write_payload(out)?;
+                }
+                out.push(TAG_END);
+            }
+            NbtTag::IntArray(values) => {
+                write_length(out, values.len())?;
+                for value in values {
+                    out.extend_from_slice(&value.to_be_bytes());
+                }
+            }
+            NbtTag::LongArray(values) => {
+                write_length(out, values.len())?;
+                for value in values {
+                    out.extend_from_slice(&value.to_be_bytes());
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn as_number(&self) -> DataResult<f64> {
+        match self {
+            NbtTag::Byte(value) => Ok(*value as f64),
+            NbtTag::Short(value) => Ok(*value as f64),
+            NbtTag::Int(value) => Ok(*value as f64),
+            NbtTag::Long(value) => Ok(*value as f64),
+            NbtTag::Float(value) => Ok(*value as f64),
+            NbtTag::Double(value) => Ok(*value),
+            _ => Err(DataError::unexpected_type("number")),
+        }
+    }
+
+    fn as_integer(&self) -> DataResult<i64> {
+        match self {
+            NbtTag::Byte(value) => Ok(*value as i64),
+            NbtTag::Short(value) => Ok(*value as i64),
+            NbtTag::Int(value) => Ok(*value as i64),
+            NbtTag::Long(value) => Ok(*value),
+            _ => self.as_number().map(|value| value as i64),
+        }
+    }
+
+    fn get_integer<I: TryFrom<i64>>(&self, expected: &str) -> DataResult<I> {
+        let value = self.as_integer()?;
+        I::try_from(value).map_err(|_| {
+            DataError::new_custom(&format!("{} is out of range for {}", value, expected))
+        })
+    }
+
+    /// Widens the elements of a list that mixes numeric tags to the widest of them, since NBT lists can only
+    /// hold one tag type. Lists mixing other tags are left as they are, and fail to serialize.
+    fn widen_list(&mut self) {
+        let NbtTag::List(values) = self else {
+            return;
+        };
+        let Some(first) = values.first().map(NbtTag::id) else {
+            return;
+        };
+        if values.iter().all(|value| value.id() == first) {
+            return;
+        }
+        let Some(mut widest) = values
+            .iter()
+            .map(|value| match value {
+                NbtTag::Byte(_)
+                | NbtTag::Short(_)
+                | NbtTag::Int(_)
+                | NbtTag::Long(_)
+                | NbtTag::Float(_)
+                | NbtTag::Double(_) => Some(value.id()),
+                _ => None,
+            })
+            .collect::<Option<Vec<_>>>()
+            .and_then(|ids| ids.into_iter().max())
+        else {
+            return;
+        };
+        // A float can't hold every int or long, so those are widened to doubles instead.
+        if widest == TAG_FLOAT
+            && values
+                .iter()
+                .any(|value| matches!(value, NbtTag::Int(_) | NbtTag::Long(_)))
+        {
+            widest = TAG_DOUBLE;
+        }
+        for value in values.iter_mut() {
+            *value = match widest {
+                TAG_SHORT => NbtTag::Short(value.as_integer().unwrap_or_default() as i16),
+                TAG_INT => NbtTag::Int(value.as_integer().unwrap_or_default() as i32),
+                TAG_LONG => NbtTag::Long(value.as_integer().unwrap_or_default()),
+                TAG_FLOAT => NbtTag::Float(value.as_number().unwrap_or_default() as f32),
+                _ => NbtTag::Double(value.as_number().unwrap_or_default()),
+            };
+        }
+    }
+
+    /// Expands a typed array into a list of tags, leaving other tags as they are.
+    fn expand_array(&mut self) -> Option<u8> {
+        let (id, values) = match self {
+            NbtTag::ByteArray(values) => (
+                TAG_BYTE_ARRAY,
+                values.iter().map(|value| NbtTag::Byte(*value)).collect(),
+            ),
+            NbtTag::IntArray(values) => (
+                TAG_INT_ARRAY,
+                values.iter().map(|value| NbtTag::Int(*value)).collect(),
+            ),
+            NbtTag::LongArray(values) => (
+                TAG_LONG_ARRAY,
+                values.iter().map(|value| NbtTag::Long(*value)).collect(),
+            ),
+            _ => return None,
+        };
+        *self = NbtTag::List(values);
+        Some(id)
+    }
+
+    /// Packs a list of tags back into the typed array `id` if every element still fits it.
+    fn pack_array(&mut self, id: u8) {
+        let NbtTag::List(values) = self else {
+            return;
+        };
+        let packed = match id {
+            TAG_BYTE_ARRAY => values
+                .iter()
+                .map(|value| match value {
+                    NbtTag::Byte(value) => Some(*value),
+                    _ => None,
+                })
+                .collect::<Option<Vec<_>>>()
+                .map(NbtTag::ByteArray),
+            TAG_INT_ARRAY => values
+                .iter()
+                .map(|value| match value {
+                    NbtTag::Int(value) => Some(*value),
+                    _ => None,
+                })
+                .collect::<Option<Vec<_>>>()
+                .map(NbtTag::IntArray),
+            TAG_LONG_ARRAY => values
+                .iter()
+                .map(|value| match value {
+                    NbtTag::Long(value) => Some(*value),
+                    _ => None,
+                })
+                .collect::<Option<Vec<_>>>()
+                .map(NbtTag::LongArray),
+            _ => None,
+        };
+        if let Some(packed) = packed {
+            *self = packed;
+        }
+    }
+}
+
+fn write_length(out: &mut Vec<u8>, length: usize) -> DataResult<()> {
+    let length = i32::try_from(length)
+        .map_err(|_| DataError::new_custom("array is too long to be written as NBT"))?;
+    out.extend_from_slice(&length.to_be_bytes());
+    Ok(())
+}
+
+/// Writes a string in the modified UTF-8 encoding used by Java's `DataOutput`, where NUL is
+/// encoded as two bytes and characters outside the BMP are encoded as surrogate pairs.
+fn write_string(out: &mut Vec<u8>, value: &str) -> DataResult<()> {
+    let mut encoded = Vec::with_capacity(value.len());
+    let mut units = [0u16; 2];
+    for c in value.chars() {
+        for unit in c.encode_utf16(&mut units) {
+            let unit = *unit as u32;
+            match unit {
+                0x01..=0x7F => encoded.push(unit as u8),
+                0x00 | 0x80..=0x7FF => {
+                    encoded.push(0xC0 | (unit >> 6) as u8);
+                    encoded.push(0x80 | (unit & 0x3F) as u8);
+                }
+                _ => {
+                    encoded.push(0xE0 | (unit >> 12) as u8);
+                    encoded.push(0x80 | ((unit >> 6) & 0x3F) as u8);
+                    encoded.push(0x80 | (unit & 0x3F) as u8);
+                }
+            }
+        }
+    }
+    let length = u16::try_from(encoded.len())
+        .map_err(|_| DataError::new_custom("string is too long to be written as NBT"))?;
+    out.extend_from_slice(&length.to_be_bytes());
+    out.extend_from_slice(&encoded);
+    Ok(())
+}
+
+struct NbtReader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> NbtReader<'a> {
+    fn take(&mut self, count: usize) -> DataResult<&'a [u8]> {
+        let end = self
+            .position
+            .checked_add(count)
+            .filter(|end| *end <= self.bytes.len())
+            .ok_or_else(|| {
+                DataError::new_custom(&format!(
+                    "unexpected end of NBT data at byte {}",
+                    self.position
+                ))
+            })?;
+        let slice = &self.bytes[self.position..end];
+        self.position = end;
+        Ok(slice)
+    }
+
+    fn take_array<const N: usize>(&mut self) -> DataResult<[u8; N]> {
+        let mut array = [0; N];
+        array.copy_from_slice(self.take(N)?);
+        Ok(array)
+    }
+
+    fn read_u8(&mut self) -> DataResult<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    /// Reads an array length, making sure the remaining input could hold that many elements of `width` bytes.
+    fn read_length(&mut self, width: usize) -> DataResult<usize> {
+        let length = i32::from_be_bytes(self.take_array()?);
+        let length = usize::try_from(length)
+            .map_err(|_| DataError::new_custom(&format!("negative length {}", length)))?;
+        if length.saturating_mul(width) > self.bytes.len() - self.position {
+            return Err(DataError::new_custom(&format!(
+                "length {} is longer than the remaining NBT data",
+                length
+            )));
+        }
+        Ok(length)
+    }
+
+    fn read_string(&mut self) -> DataResult<String> {
+        let length = u16::from_be_bytes(self.take_array()?) as usize;
+        let bytes = self.take(length)?;
+        let invalid = || DataError::new_custom("invalid modified UTF-8 in NBT string");
+
+        let mut units = Vec::with_capacity(length);
+        let mut index = 0;
+        while index < bytes.len() {
+            let first = bytes[index] as u16;
+            let continuation = |offset: usize| match bytes.get(index + offset) {
+                Some(byte) if byte & 0xC0 == 0x80 => Ok((byte & 0x3F) as u16),
+                _ => Err(invalid()),
+            };
+            match first {
+                0x00..=0x7F => {
+                    units.push(first);
+                    index += 1;
+                }
+                0xC0..=0xDF => {
+                    units.push(((first & 0x1F) << 6) | continuation(1)?);
+                    index += 2;
+                }
+                0xE0..=0xEF => {
+                    units.push(((first & 0x0F) << 12) | (continuation(1)? << 6) | continuation(2)?);
+                    index += 3;
+                }
+                _ => return Err(invalid()),
+            }
+        }
+        char::decode_utf16(units)
+            .collect::<Result<String, _>>()
+            .map_err(|_| invalid())
+    }
+
+    fn read_payload(&mut self, id: u8, depth: usize) -> DataResult<NbtTag> {
+        if depth > MAX_DEPTH {
+            return Err(DataError::new_custom(&format!(
+                "NBT data is nested deeper than {} levels",
+                MAX_DEPTH
+            )));
+        }
+        Ok(match id {
+            TAG_BYTE => NbtTag::Byte(self.read_u8()? as i8),
+            TAG_SHORT => NbtTag::Short(i16::from_be_bytes(self.take_array()?)),
+            TAG_INT => NbtTag::Int(i32::from_be_bytes(self.take_array()?)),
+            TAG_LONG => NbtTag::Long(i64::from_be_bytes(self.take_array()?)),
+            TAG_FLOAT => NbtTag::Float(f32::from_be_bytes(self.take_array()?)),
+            TAG_DOUBLE => NbtTag::Double(f64::from_be_bytes(self.take_array()?)),
+            TAG_BYTE_ARRAY => {
+                let length = self.read_length(1)?;
+                NbtTag::ByteArray(self.take(length)?.iter().map(|b| *b as i8).collect())
+            }
+            TAG_STRING => NbtTag::String(self.read_string()?),
+            TAG_LIST => {
+                let element = self.read_u8()?;
+                let length = self.read_length(if element == TAG_END { 0 } else { 1 })?;
+                if element == TAG_END && length > 0 {
+                    return Err(DataError::new_custom("list of end tags must be empty"));
+                }
+                let mut values = Vec::with_capacity(length);
+                for _ in 0..length {
+                    values.push(self.read_payload(element, depth + 1)?);
+                }
+                NbtTag::List(values)
+            }
+            TAG_COMPOUND => {
+                let mut entries = BTreeMap::new();
+                loop {
+                    let id = self.read_u8()?;
+                    if id == TAG_END {
+                        break;
+                    }
+                    let key = self.read_string()?;
+                    let value = self.read_payload(id, depth + 1)?;
+                    entries.insert(key, value);
+                }
+                NbtTag::Compound(entries)
+            }
+            TAG_INT_ARRAY => {
+                let length = self.read_length(4)?;
+                let mut values = Vec::with_capacity(length);
+                for _ in 0..length {
+                    values.push(i32::from_be_bytes(self.take_array()?));
+                }
+                NbtTag::IntArray(values)
+            }
+            TAG_LONG_ARRAY => {
+                let length = self.read_length(8)?;
+                let mut values = Vec::with_capacity(length);
+                for _ in 0..length {
+                    values.push(i64::from_be_bytes(self.take_array()?));
+                }
+                NbtTag::LongArray(values)
+            }
+            _ => {
+                return Err(DataError::new_custom(&format!(
+                    "unknown NBT tag type {}",
+                    id
+                )));
+            }
+        })
+    }
+}
+
+/// A [`CodecOps`] for [`NbtTag`]s.
+///
+/// Like DataFixerUpper's `NbtOps`, numbers are read from any numeric tag regardless of width, with floating point
+/// numbers rounded toward zero when read as integers. Unlike DataFixerUpper, reading an integer that doesn't fit the
+/// requested width is an error rather than truncating it.
+///
+/// Lists made up entirely of bytes, ints or longs are created as the matching typed array.
+/// Typed arrays can be viewed and modified as lists, and numbers of different widths written into a list are
+/// widened to a single tag type.
+#[derive(Debug, Clone)]
+pub struct NbtOps;
+
+impl CodecOps for NbtOps {
+    type T = NbtTag;
+
+    fn create_double(&self, value: &f64) -> NbtTag {
+        NbtTag::Double(*value)
+    }
+
+    fn create_float(&self, value: &f32) -> NbtTag {
+        NbtTag::Float(*value)
+    }
+
+    fn create_byte(&self, value: &i8) -> NbtTag {
+        NbtTag::Byte(*value)
+    }
+
+    fn create_short(&self, value: &i16) -> NbtTag {
+        NbtTag::Short(*value)
+    }
+
+    fn create_int(&self, value: &i32) -> NbtTag {
+        NbtTag::Int(*value)
+    }
+
+    fn create_long(&self, value: &i64) -> NbtTag {
+        NbtTag::Long(*value)
+    }
+
+    fn create_string(&self, value: &str) -> NbtTag {
+        NbtTag::String(value.to_string())
+    }
+
+    fn create_boolean(&self, value: &bool) -> NbtTag {
+        NbtTag::Byte(*value as i8)
+    }
+
+    fn create_list(&self, value: impl IntoIterator<Item = NbtTag>) -> NbtTag {
+        let values = value.into_iter().collect::<Vec<_>>();
+        let mut list = NbtTag::List(values);
+        for id in [TAG_BYTE_ARRAY, TAG_INT_ARRAY, TAG_LONG_ARRAY] {
+            if matches!(&list, NbtTag::List(values) if !values.is_empty()) {
+                list.pack_array(id);
+            }
+        }
+        list
+    }
+
+    fn create_map(&self, pairs: impl IntoIterator<Item = (String, NbtTag)>) -> NbtTag {
+        NbtTag::Compound(pairs.into_iter().collect())
+    }
+
+    fn create_unit(&self) -> NbtTag {
+        NbtTag::Compound(BTreeMap::new())
+    }
+
+    fn get_float(&self, value: &NbtTag) -> DataResult<f32> {
+        match value {
+            NbtTag::Float(value) => Ok(*value),
+            _ => value.as_number().map(|value| value as f32),
+        }
+    }
+
+    fn get_double(&self, value: &NbtTag) -> DataResult<f64> {
+        value.as_number()
+    }
+
+    fn get_byte(&self, value: &NbtTag) -> DataResult<i8> {
+        value.get_integer("byte")
+    }
+
+    fn get_short(&self, value: &NbtTag) -> DataResult<i16> {
+        value.get_integer("short")
+    }
+
+    fn get_int(&self, value: &NbtTag) -> DataResult<i32> {
+        value.get_integer("int")
+    }
+
+    fn get_long(&self, value: &NbtTag) -> DataResult<i64> {
+        value.as_integer()
+    }
+
+    fn get_string(&self, value: &NbtTag) -> DataResult<String> {
+        match value {
+            NbtTag::String(string) => Ok(string.clone()),
+            _ => Err(DataError::unexpected_type("string")),
+        }
+    }
+
+    fn get_boolean(&self, value: &NbtTag) -> DataResult<bool> {
+        value
+            .as_integer()
+            .map(|value| value != 0)
+            .map_err(|_| DataError::unexpected_type("byte"))
+    }
+
+    fn get_list(&self, value: &NbtTag) -> DataResult<impl ListView<NbtTag>> {
+        match value {
+            NbtTag::List(values) => Ok(NbtListView::Borrowed(values)),
+            NbtTag::ByteArray(_) | NbtTag::IntArray(_) | NbtTag::LongArray(_) => {
+                let mut expanded = value.clone();
+                expanded.expand_array();
+                let NbtTag::List(values) = expanded else {
+                    unreachable!()
+                };
+                Ok(NbtListView::Owned(values))
+            }
+            _ => Err(DataError::unexpected_type("list")),
+        }
+    }
+
+    fn get_list_mut(&self, value: &mut NbtTag) -> DataResult<impl ListViewMut<NbtTag>> {
+        let array = value.expand_array();
+        match value {
+            NbtTag::List(_) => Ok(NbtListViewMut {
+                inner: value,
+                array,
+            }),
+            _ => Err(DataError::unexpected_type("list")),
+        }
+    }
+
+    fn get_map(&self, value: &NbtTag) -> DataResult<impl MapView<NbtTag>> {
+        match value {
+            NbtTag::Compound(entries) => Ok(NbtCompoundView { inner: entries }),
+            _ => Err(DataError::unexpected_type("compound")),
+        }
+    }
+
+    fn get_map_mut(&self, value: &mut NbtTag) -> DataResult<impl MapViewMut<NbtTag>> {
+        match value {
+            NbtTag::Compound(entries) => Ok(NbtCompoundViewMut { inner: entries }),
+            _ => Err(DataError::unexpected_type("compound")),
+        }
+    }
+
+    fn kind(&self, value: &NbtTag) -> ValueKind {
+        match value {
+            NbtTag::Byte(_) => ValueKind::Number(NumberKind::Byte),
+            NbtTag::Short(_) => ValueKind::Number(NumberKind::Short),
+            NbtTag::Int(_) => ValueKind::Number(NumberKind::Int),
+            NbtTag::Long(_) => ValueKind::Number(NumberKind::Long),
+            NbtTag::Float(_) => ValueKind::Number(NumberKind::Float),
+            NbtTag::Double(_) => ValueKind::Number(NumberKind::Double),
+            NbtTag::String(_) => ValueKind::String,
+            NbtTag::List(_) | NbtTag::ByteArray(_) | NbtTag::IntArray(_) | NbtTag::LongArray(_) => {
+                ValueKind::List
+            }
+            NbtTag::Compound(_) => ValueKind::Map,
+        }
+    }
+
+    fn get_unit(&self, value: &NbtTag) -> DataResult<()> {
+        let NbtTag::Compound(entries) = value else {
+            return Err(DataError::unexpected_type("compound"));
+        };
+        if entries.is_empty() {
+            Ok(())
+        } else {
+            Err(DataError::new_custom("compound must have 0 fields"))
+        }
+    }
+}
+
+struct NbtCompoundView<'a> {
+    inner: &'a BTreeMap<String, NbtTag>,
+}
+
+impl MapView<NbtTag> for NbtCompoundView<'_> {
+    fn get(&self, name: &str) -> DataResult<&NbtTag> {
+        self.inner
+            .get(name)
+            .ok_or_else(|| DataError::key_not_found(name))
+    }
+
+    fn keys(&self) -> Vec<String> {
+        self.inner.keys().cloned().collect()
+    }
+}
+
+struct NbtCompoundViewMut<'a> {
+    inner: &'a mut BTreeMap<String, NbtTag>,
+}
+
+impl MapView<NbtTag> for NbtCompoundViewMut<'_> {
+    fn get(&self, name: &str) -> DataResult<&NbtTag> {
+        self.inner
+            .get(name)
+            .ok_or_else(|| DataError::key_not_found(name))
+    }
+
+    fn keys(&self) -> Vec<String> {
+        self.inner.keys().cloned().collect()
+    }
+}
+
+impl MapViewMut<NbtTag> for NbtCompoundViewMut<'_> {
+    fn get_mut(&mut self, name: &str) -> DataResult<&mut NbtTag> {
+        self.inner
+            .get_mut(name)
+            .ok_or_else(|| DataError::key_not_found(name))
+    }
+
+    fn set(&mut self, name: &str, value: NbtTag) {
+        self.inner.insert(name.to_string(), value);
+    }
+
+    fn remove(&mut self, key: &str) -> DataResult<NbtTag> {
+        self.inner
+            .remove(key)
+            .ok_or_else(|| DataError::key_not_found(key))
+    }
+}
+
+/// Typed arrays don't hold [`NbtTag`]s, so viewing one as a list copies its elements out.
+enum NbtListView<'a> {
+    Borrowed(&'a [NbtTag]),
+    Owned(Vec<NbtTag>),
+}
+
+impl ListView<NbtTag> for NbtListView<'_> {
+    fn get(&self, index: usize) -> DataResult<&NbtTag> {
+        let values = match self {
+            NbtListView::Borrowed(values) => *values,
+            NbtListView::Owned(values) => values.as_slice(),
+        };
+        values
+            .get(index)
+            .ok_or(DataError::list_index_out_of_bounds(index, values.len()))
+    }
+
+    fn into_iter(self) -> impl Iterator<Item = NbtTag> {
+        match self {
+            NbtListView::Borrowed(values) => values.to_vec(),
+            NbtListView::Owned(values) => values,
+        }
+        .into_iter()
+    }
+}
+
+/// A mutable view into a list. Typed arrays are expanded into a list while the view is alive. When the view is
+/// dropped, numbers of different widths are widened to one tag type, and typed arrays are packed back into
+/// the array matching their elements if there is one.
+struct NbtListViewMut<'a> {
+    inner: &'a mut NbtTag,
+    array: Option<u8>,
+}
+
+impl ListViewMut<NbtTag> for NbtListViewMut<'_> {
+    fn append(&mut self, value: NbtTag) {
+        let NbtTag::List(values) = self.inner else {
+            return;
+        };
+        values.push(value);
+    }
+
+    fn get_mut(&mut self, index: usize) -> DataResult<&mut NbtTag> {
+        let NbtTag::List(values) = self.inner else {
+            return Err(DataError::unexpected_type("list"));
+        };
+        let len = values.len();
+        values
+            .get_mut(index)
+            .ok_or(DataError::list_index_out_of_bounds(index, len))
+    }
+}
+
+impl Drop for NbtListViewMut<'_> {
+    fn drop(&mut self) {
+        self.inner.widen_list();
+        if let Some(id) = self.array {
+            for id in [id, TAG_BYTE_ARRAY, TAG_INT_ARRAY, TAG_LONG_ARRAY] {
+                self.inner.pack_array(id);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{string::String, vec, vec::Vec};
+
+    use crate::serialization::{
+        Codec, CodecAdapters, CodecOps, DefaultCodec, ListView, ListViewMut, MapCodecBuilder,
+    };
+
+    use super::{NbtOps, NbtTag};
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct Chunk {
+        x: i32,
+        z: i32,
+        status: String,
+        heightmap: Vec<i64>,
+        biomes: Vec<i8>,
+    }
+
+    impl<O: CodecOps> DefaultCodec<O> for Chunk {
+        fn codec() -> impl Codec<Self, O> {
+            MapCodecBuilder::new()
+                .field(i32::codec().field_of("x", |c: &Chunk| &c.x))
+                .field(i32::codec().field_of("z", |c: &Chunk| &c.z))
+                .field(String::codec().field_of("status", |c: &Chunk| &c.status))
+                .field(
+                    i64::codec()
+                        .list_of()
+                        .field_of("heightmap", |c: &Chunk| &c.heightmap),
+                )
+                .field(
+                    i8::codec()
+                        .list_of()
+                        .field_of("biomes", |c: &Chunk| &c.biomes),
+                )
+                .build(|x, z, status, heightmap, biomes| Chunk {
+                    x,
+                    z,
+                    status,
+                    heightmap,
+                    biomes,
+                })
+        }
+    }
+
+    #[test]
+    fn binary_round_trip() {
+        let chunk = Chunk {
+            x: -3,
+            z: 7,
+            status: String::from("minecraft:full \0 \u{1F600}"),
+            heightmap: vec![1, -2, i64::MAX],
+            biomes: vec![4, 5],
+        };
+        let encoded = Chunk::codec().encode_start(&NbtOps, &chunk).unwrap();
+        let NbtTag::Compound(entries) = &encoded else {
+            panic!("expected a compound");
+        };
+        assert_eq!(
+            entries["heightmap"],
+            NbtTag::LongArray(vec![1, -2, i64::MAX])
+        );
+        assert_eq!(entries["biomes"], NbtTag::ByteArray(vec![4, 5]));
+
+        let bytes = encoded.to_bytes("Level").unwrap();
+        assert_eq!(&bytes[..8], &[10, 0, 5, b'L', b'e', b'v', b'e', b'l']);
+        let (name, decoded) = NbtTag::from_bytes(&bytes).unwrap();
+        assert_eq!(name, "Level");
+        assert_eq!(decoded, encoded);
+        assert_eq!(
+            Chunk::codec().decode_start(&NbtOps, &decoded).unwrap(),
+            chunk
+        );
+
+        assert!(NbtTag::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+        assert!(
+            NbtTag::List(vec![NbtTag::Int(1), NbtTag::Byte(1)])
+                .to_bytes("")
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn typed_arrays_as_lists() {
+        let mut array = NbtTag::IntArray(vec![1, 2]);
+        assert_eq!(
+            NbtOps.get_list(&array).unwrap().get(1).unwrap(),
+            &NbtTag::Int(2)
+        );
+
+        NbtOps
+            .get_list_mut(&mut array)
+            .unwrap()
+            .append(NbtTag::Int(3));
+        assert_eq!(array, NbtTag::IntArray(vec![1, 2, 3]));
+
+        // A long doesn't fit in an int array, so every element is widened into a long array.
+        *NbtOps.get_list_mut(&mut array).unwrap().get_mut(0).unwrap() = NbtTag::Long(1);
+        assert_eq!(array, NbtTag::LongArray(vec![1, 2, 3]));
+        assert!(array.to_bytes("").is_ok());
+
+        let mut list = NbtTag::List(vec![NbtTag::Short(1)]);
+        NbtOps
+            .get_list_mut(&mut list)
+            .unwrap()
+            .append(NbtTag::Float(0.5));
+        assert_eq!(
+            list,
+            NbtTag::List(vec![NbtTag::Float(1.0), NbtTag::Float(0.5)])
+        );
+        assert!(list.to_bytes("").is_ok());
+    }
+
+    #[test]
+    fn integer_ranges() {
+        assert_eq!(NbtOps.get_byte(&NbtTag::Long(-3)).unwrap(), -3);
+        assert_eq!(NbtOps.get_short(&NbtTag::Int(300)).unwrap(), 300);
+        assert!(NbtOps.get_byte(&NbtTag::Int(300)).is_err());
+        assert!(NbtOps.get_int(&NbtTag::Long(i64::MAX)).is_err());
+        assert_eq!(NbtOps.get_int(&NbtTag::Double(2.5)).unwrap(), 2);
+    }
+}
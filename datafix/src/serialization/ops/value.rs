@@ -0,0 +1,415 @@
+use alloc::{
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
+
+use crate::{
+    result::{DataError, DataResult},
+    serialization::{CodecOps, ListView, MapView},
+};
+
+use super::{ListViewMut, MapViewMut, NumberKind, ValueKind};
+
+/// A format-agnostic value, for running rules on data without picking a serialization format.
+///
+/// Unlike `JsonValue`, every numeric width is kept as its own variant, and maps keep their keys
+/// in the order they were inserted.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Byte(i8),
+    Short(i16),
+    Int(i32),
+    Long(i64),
+    Float(f32),
+    Double(f64),
+    Bool(bool),
+    String(String),
+    List(Vec<Value>),
+    Map(Vec<(String, Value)>),
+    Unit,
+}
+
+impl Value {
+    fn as_integer(&self) -> Option<i64> {
+        match self {
+            Value::Byte(value) => Some(*value as i64),
+            Value::Short(value) => Some(*value as i64),
+            Value::Int(value) => Some(*value as i64),
+            Value::Long(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    fn as_number(&self) -> DataResult<f64> {
+        match self {
+            Value::Float(value) => Ok(*value as f64),
+            Value::Double(value) => Ok(*value),
+            _ => self
+                .as_integer()
+                .map(|value| value as f64)
+                .ok_or_else(|| DataError::unexpected_type("number")),
+        }
+    }
+
+    /// Reads an integer of any width that fits into `I` without losing information.
+    fn get_integer<I: TryFrom<i64>>(&self, expected: &str) -> DataResult<I> {
+        let value = self
+            .as_integer()
+            .ok_or_else(|| DataError::unexpected_type(expected))?;
+        I::try_from(value).map_err(|_| {
+            DataError::new_custom(&format!("{} is out of range for {}", value, expected))
+        })
+    }
+}
+
+/// A [`CodecOps`] for [`Value`]s.
+///
+/// Integers can be read as any width they fit into, so an `i8` can be decoded from a [`Value::Long`] holding `3`,
+/// but not from one holding `300`. Floating point numbers can be read from any numeric value.
+#[derive(Debug, Clone)]
+pub struct ValueOps;
+
+impl CodecOps for ValueOps {
+    type T = Value;
+
+    fn create_double(&self, value: &f64) -> Value {
+        Value::Double(*value)
+    }
+
+    fn create_float(&self, value: &f32) -> Value {
+        Value::Float(*value)
+    }
+
+    fn create_byte(&self, value: &i8) -> Value {
+        Value::Byte(*value)
+    }
+
+    fn create_short(&self, value: &i16) -> Value {
+        Value::Short(*value)
+    }
+
+    fn create_int(&self, value: &i32) -> Value {
+        Value::Int(*value)
+    }
+
+    fn create_long(&self, value: &i64) -> Value {
+        Value::Long(*value)
+    }
+
+    fn create_string(&self, value: &str) -> Value {
+        Value::String(value.to_string())
+    }
+
+    fn create_boolean(&self, value: &bool) -> Value {
+        Value::Bool(*value)
+    }
+
+    fn create_list(&self, value: impl IntoIterator<Item = Value>) -> Value {
+        Value::List(value.into_iter().collect())
+    }
+
+    fn create_map(&self, pairs: impl IntoIterator<Item = (String, Value)>) -> Value {
+        let mut map = Value::Map(Vec::new());
+        {
+            let mut view = ValueMapViewMut { inner: &mut map };
+            for (key, value) in pairs {
+                view.set(&key, value);
+            }
+        }
+        map
+    }
+
+    fn create_unit(&self) -> Value {
+        Value::Unit
+    }
+
+    fn get_float(&self, value: &Value) -> DataResult<f32> {
+        match value {
+            Value::Float(value) => Ok(*value),
+            _ => value.as_number().map(|value| value as f32),
+        }
+    }
+
+    fn get_double(&self, value: &Value) -> DataResult<f64> {
+        value.as_number()
+    }
+
+    fn get_byte(&self, value: &Value) -> DataResult<i8> {
+        value.get_integer("byte")
+    }
+
+    fn get_short(&self, value: &Value) -> DataResult<i16> {
+        value.get_integer("short")
+    }
+
+    fn get_int(&self, value: &Value) -> DataResult<i32> {
+        value.get_integer("int")
+    }
+
+    fn get_long(&self, value: &Value) -> DataResult<i64> {
+        value.get_integer("long")
+    }
+
+    fn get_string(&self, value: &Value) -> DataResult<String> {
+        match value {
+            Value::String(string) => Ok(string.clone()),
+            _ => Err(DataError::unexpected_type("string")),
+        }
+    }
+
+    fn get_boolean(&self, value: &Value) -> DataResult<bool> {
+        match value {
+            Value::Bool(boolean) => Ok(*boolean),
+            _ => Err(DataError::unexpected_type("boolean")),
+        }
+    }
+
+    fn get_list(&self, value: &Value) -> DataResult<impl ListView<Value>> {
+        match value {
+            Value::List(values) => Ok(ValueListView { inner: values }),
+            _ => Err(DataError::unexpected_type("list")),
+        }
+    }
+
+    fn get_list_mut(&self, value: &mut Value) -> DataResult<impl ListViewMut<Value>> {
+        match value {
+            Value::List(values) => Ok(ValueListViewMut { inner: values }),
+            _ => Err(DataError::unexpected_type("list")),
+        }
+    }
+
+    fn get_map(&self, value: &Value) -> DataResult<impl MapView<Value>> {
+        match value {
+            Value::Map(_) => Ok(ValueMapView { inner: value }),
+            _ => Err(DataError::unexpected_type("map")),
+        }
+    }
+
+    fn get_map_mut(&self, value: &mut Value) -> DataResult<impl MapViewMut<Value>> {
+        match value {
+            Value::Map(_) => Ok(ValueMapViewMut { inner: value }),
+            _ => Err(DataError::unexpected_type("map")),
+        }
+    }
+
+    fn kind(&self, value: &Value) -> ValueKind {
+        match value {
+            Value::Byte(_) => ValueKind::Number(NumberKind::Byte),
+            Value::Short(_) => ValueKind::Number(NumberKind::Short),
+            Value::Int(_) => ValueKind::Number(NumberKind::Int),
+            Value::Long(_) => ValueKind::Number(NumberKind::Long),
+            Value::Float(_) => ValueKind::Number(NumberKind::Float),
+            Value::Double(_) => ValueKind::Number(NumberKind::Double),
+            Value::Bool(_) => ValueKind::Bool,
+            Value::String(_) => ValueKind::String,
+            Value::List(_) => ValueKind::List,
+            Value::Map(_) => ValueKind::Map,
+            Value::Unit => ValueKind::Unit,
+        }
+    }
+
+    fn get_unit(&self, value: &Value) -> DataResult<()> {
+        match value {
+            Value::Unit => Ok(()),
+            Value::Map(entries) if entries.is_empty() => Ok(()),
+            Value::Map(_) => Err(DataError::new_custom("map must have 0 fields")),
+            _ => Err(DataError::unexpected_type("unit")),
+        }
+    }
+}
+
+fn map_get<'a>(value: &'a Value, name: &str) -> DataResult<&'a Value> {
+    let Value::Map(entries) = value else {
+        return Err(DataError::unexpected_type("map"));
+    };
+    entries
+        .iter()
+        .find(|(key, _)| key == name)
+        .map(|(_, value)| value)
+        .ok_or_else(|| DataError::key_not_found(name))
+}
+
+fn map_keys(value: &Value) -> Vec<String> {
+    let Value::Map(entries) = value else {
+        return Vec::new();
+    };
+    entries.iter().map(|(key, _)| key.clone()).collect()
+}
+
+struct ValueMapView<'a> {
+    inner: &'a Value,
+}
+
+impl MapView<Value> for ValueMapView<'_> {
+    fn get(&self, name: &str) -> DataResult<&Value> {
+        map_get(self.inner, name)
+    }
+
+    fn keys(&self) -> Vec<String> {
+        map_keys(self.inner)
+    }
+}
+
+struct ValueMapViewMut<'a> {
+    inner: &'a mut Value,
+}
+
+impl MapView<Value> for ValueMapViewMut<'_> {
+    fn get(&self, name: &str) -> DataResult<&Value> {
+        map_get(self.inner, name)
+    }
+
+    fn keys(&self) -> Vec<String> {
+        map_keys(self.inner)
+    }
+}
+
+impl MapViewMut<Value> for ValueMapViewMut<'_> {
+    fn get_mut(&mut self, name: &str) -> DataResult<&mut Value> {
+        let Value::Map(entries) = self.inner else {
+            return Err(DataError::unexpected_type("map"));
+        };
+        entries
+            .iter_mut()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value)
+            .ok_or_else(|| DataError::key_not_found(name))
+    }
+
+    /// Replaces the value in place if the key is already present, otherwise appends it to the end of the map.
+    fn set(&mut self, name: &str, value: Value) {
+        let Value::Map(entries) = self.inner else {
+            return;
+        };
+        match entries.iter_mut().find(|(key, _)| key == name) {
+            Some((_, existing)) => *existing = value,
+            None => entries.push((name.to_string(), value)),
+        }
+    }
+
+    fn remove(&mut self, key: &str) -> DataResult<Value> {
+        let Value::Map(entries) = self.inner else {
+            return Err(DataError::unexpected_type("map"));
+        };
+        let index = entries
+            .iter()
+            .position(|(name, _)| name == key)
+            .ok_or_else(|| DataError::key_not_found(key))?;
+        Ok(entries.remove(index).1)
+    }
+}
+
+struct ValueListView<'a> {
+    inner: &'a Vec<Value>,
+}
+
+impl ListView<Value> for ValueListView<'_> {
+    fn get(&self, index: usize) -> DataResult<&Value> {
+        self.inner
+            .get(index)
+            .ok_or(DataError::list_index_out_of_bounds(index, self.inner.len()))
+    }
+
+    fn into_iter(self) -> impl Iterator<Item = Value> {
+        self.inner.clone().into_iter()
+    }
+}
+
+struct ValueListViewMut<'a> {
+    inner: &'a mut Vec<Value>,
+}
+
+impl ListViewMut<Value> for ValueListViewMut<'_> {
+    fn append(&mut self, value: Value) {
+        self.inner.push(value);
+    }
+
+    fn get_mut(&mut self, index: usize) -> DataResult<&mut Value> {
+        let len = self.inner.len();
+        self.inner
+            .get_mut(index)
+            .ok_or(DataError::list_index_out_of_bounds(index, len))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{string::String, vec, vec::Vec};
+
+    use crate::{
+        fixers::Rules,
+        serialization::{Codec, CodecAdapters, CodecOps, DefaultCodec, MapCodecBuilder, MapView},
+    };
+
+    use super::{Value, ValueOps};
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct Sample {
+        name: String,
+        flags: i8,
+        weight: f32,
+        ticks: Vec<i64>,
+    }
+
+    impl<O: CodecOps> DefaultCodec<O> for Sample {
+        fn codec() -> impl Codec<Self, O> {
+            MapCodecBuilder::new()
+                .field(String::codec().field_of("name", |s: &Sample| &s.name))
+                .field(i8::codec().field_of("flags", |s: &Sample| &s.flags))
+                .field(f32::codec().field_of("weight", |s: &Sample| &s.weight))
+                .field(
+                    i64::codec()
+                        .list_of()
+                        .field_of("ticks", |s: &Sample| &s.ticks),
+                )
+                .build(|name, flags, weight, ticks| Sample {
+                    name,
+                    flags,
+                    weight,
+                    ticks,
+                })
+        }
+    }
+
+    #[test]
+    fn preserves_widths_and_order() {
+        let sample = Sample {
+            name: "probe".into(),
+            flags: 3,
+            weight: 0.5,
+            ticks: vec![1, i64::MAX],
+        };
+        let encoded = Sample::codec().encode_start(&ValueOps, &sample).unwrap();
+        assert_eq!(
+            encoded,
+            Value::Map(vec![
+                ("name".into(), Value::String("probe".into())),
+                ("flags".into(), Value::Byte(3)),
+                ("weight".into(), Value::Float(0.5)),
+                (
+                    "ticks".into(),
+                    Value::List(vec![Value::Long(1), Value::Long(i64::MAX)])
+                ),
+            ])
+        );
+        assert_eq!(
+            Sample::codec().decode_start(&ValueOps, &encoded).unwrap(),
+            sample
+        );
+
+        let renamed = ValueOps.repair(encoded, Rules::rename_field("flags", "bits"));
+        assert_eq!(
+            ValueOps.get_map(&renamed).unwrap().keys(),
+            ["name", "weight", "ticks", "bits"]
+        );
+    }
+
+    #[test]
+    fn integers_must_fit() {
+        assert_eq!(ValueOps.get_byte(&Value::Long(3)).unwrap(), 3);
+        assert!(ValueOps.get_byte(&Value::Long(300)).is_err());
+        assert!(ValueOps.get_int(&Value::Double(1.0)).is_err());
+        assert_eq!(ValueOps.get_double(&Value::Int(2)).unwrap(), 2.0);
+    }
+}
@@ -45,12 +45,11 @@ fn main() -> CodecResult<()> {
     let list = Nested::new(10).with(20).with(30);
     let mut encoded = Nested::codec().encode_start(&JsonOps, &list)?;
     println!("{}", encoded.pretty(4));
-    if let JsonValue::Object(object) = &mut encoded {
-        if let JsonValue::Object(object) = object.get_mut("next").unwrap() {
-            if let JsonValue::Object(object) = object.get_mut("next").unwrap() {
-                object.insert("value", "hi".into());
-            }
-        }
+    if let JsonValue::Object(object) = &mut encoded
+        && let JsonValue::Object(object) = object.get_mut("next").unwrap()
+        && let JsonValue::Object(object) = object.get_mut("next").unwrap()
+    {
+        object.insert("value", "hi".into());
     }
     let decoded = Nested::codec().decode_start(&JsonOps, &encoded)?;
     assert_eq!(list, decoded);